@@ -3,7 +3,7 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use Fundraising::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, VestingParameter, UserInfo };
+use Fundraising::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, VestingParameter, UserInfo, Cw20HookMsg };
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -16,4 +16,5 @@ fn main() {
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(VestingParameter), &out_dir);
     export_schema(&schema_for!(UserInfo), &out_dir);
+    export_schema(&schema_for!(Cw20HookMsg), &out_dir);
 }
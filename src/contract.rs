@@ -2,21 +2,65 @@
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    Addr, to_binary, DepsMut, Env, MessageInfo, Response,
-    Uint128, CosmosMsg, WasmMsg
+    Addr, Api, to_binary, from_binary, Deps, DepsMut, Env, MessageInfo, Response, Storage, Order, Binary,
+    Uint128, WasmMsg, SubMsg, Reply, StdResult, StdError, BankMsg, Coin, Decimal, CosmosMsg,
+    Fraction, Uint256, Event, QueryRequest, WasmQuery, SystemResult, ContractResult, Empty, to_vec,
 };
+use std::convert::TryFrom;
+use std::collections::BTreeMap;
+use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, BalanceResponse as Cw20BalanceResponse};
 use cw2::set_contract_version;
-use cw20::{Cw20QueryMsg, BalanceResponse as Cw20BalanceResponse, TokenInfoResponse};
+use cw_storage_plus::{Bound, U64Key};
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::{ ExecuteMsg, InstantiateMsg, ProjectInfo, UserInfo, VestingParameter, Config};
-use crate::state::{PROJECT_INFOS, OWNER, VESTING_ADDR };
-use crate::vesting::{ ExecuteMsg as vestingExecuteMsg };
+use crate::msg::{ ExecuteMsg, InstantiateMsg, ProjectInfo, UserInfo, VestingParameter, Config,
+    SyncState, VestingUserSync, TreasuryParams, Treasury, Milestone, VotingConfig, Proposal,
+    ProposalStatus, WindDown, MoneyMarketConfig, MarketPosition, YieldCheckpoint, FeeConfig, FeeRates,
+    FeesResponse, StageSale, SaleType, Bid, AuctionState, AuctionPhase, WhitelistEntry, LotteryEntry,
+    ProjectTiers, TierAllocation, TierAllocationResponse, Tier, RegistrationConfig, Registrant, KycConfig,
+    KycScheme, KycProof, KycPayload, ReferralRates, BonusRules, BonusResponse, LiquidityConfig,
+    LiquidityPosition, ImportMode, ProjectMetadata, Cw20HookMsg};
+use crate::state::{PROJECT_INFOS, OWNER, VESTING_ADDR, VESTING_CODE_ID,
+    PENDING_VESTING_PROJECT, PROJECT_VESTING_ADDRS, VESTING_SYNC, VESTING_USER_SYNC,
+    TREASURIES, CONTRIBUTIONS, VOTING_CONFIG, PROPOSAL_COUNT, PROPOSALS, VOTES, WIND_DOWNS,
+    WIND_DOWN_WEIGHTS, REFUND_CLAIMED, MONEY_MARKET, MARKET_POSITIONS, YIELD_CHECKPOINTS, FEE_CONFIG, PROJECT_FEES,
+    FEES_PAID, SALE_FINALIZED, STAGE_SALES, OVERFLOW_STATUS, OVERFLOW_DEPOSITS,
+    TOKENS_SOLD, BIDS, BIDDER_TOTALS, AUCTION_STATES, LOTTERIES, LOTTERY_ENTRIES, TICKET_RANGES, LOTTERY_SWAPS,
+    STAKING_CONTRACT, PROJECT_TIERS, REGISTRATIONS, REGISTRANTS, REGISTERED_WEIGHT, KYC_CONFIG,
+    KYC_REVOKED, COMPLIANCE, DENY_LIST, PROJECT_DENY_LIST, REFERRAL_RATES, REFERRAL_REWARDS,
+    REFERRER_RANKS, OVERFLOW_REFERRERS, BONUS_RULES, LIQUIDITY_CONFIGS, LIQUIDITY_POSITIONS,
+    PENDING_LIQUIDITY_PROJECT, PENDING_LP_BALANCE, CANCELLED_PROJECTS, ARCHIVED_PROJECTS, PROJECT_COUNT, PROJECT_METADATA,
+    PROJECT_TOKENS };
+use crate::vesting::{ ExecuteMsg as vestingExecuteMsg, InstantiateMsg as vestingInstantiateMsg };
+use crate::staking::{ QueryMsg as stakingQueryMsg, StakedResponse };
+use crate::money_market::{ ExecuteMsg as marketExecuteMsg, QueryMsg as marketQueryMsg,
+    ExchangeRateResponse };
+use crate::events;
+use crate::dex::{ AssetInfo, Asset, PairInfo, FactoryExecuteMsg, FactoryQueryMsg, PairExecuteMsg };
 
 // version info for migration info
 const CONTRACT_NAME: &str = "Vesting";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+pub const INSTANTIATE_VESTING_REPLY_ID: u64 = 1;
+pub const VESTING_ADD_PROJECT_REPLY_ID: u64 = 2;
+pub const VESTING_SET_PROJECT_INFO_REPLY_ID: u64 = 3;
+pub const LIQUIDITY_CREATE_PAIR_REPLY_ID: u64 = 4;
+pub const LIQUIDITY_PROVIDE_REPLY_ID: u64 = 5;
+
+const DEFAULT_SYNC_LIMIT: u32 = 30;
+const MAX_SYNC_LIMIT: u32 = 100;
+const MAX_IMPORT_ENTRIES: usize = 500;
+//time after a lottery stage ends for the seed to be revealed
+const LOTTERY_REVEAL_PERIOD: u64 = 3 * 24 * 60 * 60;
+const MAX_VOTING_PERIOD: u64 = 365 * 24 * 60 * 60;
+const MAX_NAME_LENGTH: usize = 64;
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
+const MAX_URI_LENGTH: usize = 256;
+const MAX_SOCIAL_LINKS: usize = 10;
+const STAGES: [&str; 3] = ["seed", "presale", "ido"];
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -26,90 +70,2989 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    let owner = msg
-        .admin
-        .and_then(|s| deps.api.addr_validate(s.as_str()).ok()) 
-        .unwrap_or(info.sender.clone());
-    OWNER.save(deps.storage, &owner)?;
+    let owner = match msg.admin {
+        Some(admin) => check_addr(deps.api, &admin)?,
+        None => info.sender,
+    };
+    OWNER.save(deps.storage, &owner)?;
+
+    if let Some(code_id) = msg.vesting_code_id {
+        VESTING_CODE_ID.save(deps.storage, &code_id)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::SetConfig{ admin, vesting_addr, vesting_code_id }
+            => try_setconfig(deps, info, admin, vesting_addr, vesting_code_id ),
+
+        ExecuteMsg::AddProject{ project_id, admin, token_addr, vesting_params, start_time, treasury,
+            metadata }
+            => try_addproject(deps, _env, info, project_id, admin, token_addr, vesting_params, start_time,
+                treasury, metadata ),
+
+        ExecuteMsg::SetProjectMetadata{ project_id, metadata }
+            => try_setprojectmetadata(deps, info, project_id, metadata),
+
+        ExecuteMsg::SetProjectConfig{ project_id, admin, token_addr, start_time} 
+            => try_setprojectconfig(deps, info, project_id, admin, token_addr, start_time),
+
+        ExecuteMsg::AddUser{ project_id, wallet, stage, amount} 
+            => try_adduser(deps, _env, info, project_id, wallet, stage, amount),
+
+        ExecuteMsg::SetVestingParameters{ project_id, params }
+            => try_setvestingparameters(deps, info, project_id, params),
+
+        ExecuteMsg::SetSeedUsers { project_id, user_infos } 
+            =>  try_setseedusers(deps, info, project_id, user_infos),
+
+        ExecuteMsg::AddSeedUser { project_id, wallet, amount } 
+            =>  try_addseeduser(deps, _env, info, project_id, wallet, amount),
+
+        ExecuteMsg::SetPresaleUsers { project_id, user_infos } 
+            =>  try_setpresaleusers(deps, info, project_id, user_infos),
+
+        ExecuteMsg::AddPresaleUser { project_id, wallet, amount } 
+            =>  try_addpresaleuser(deps, _env, info, project_id, wallet, amount),
+
+        ExecuteMsg::SetIDOUsers { project_id, user_infos } 
+            =>  try_setidousers(deps, info, project_id, user_infos),
+
+        ExecuteMsg::AddIDOUser { project_id, wallet, amount } 
+            =>  try_addidouser(deps, _env, info, project_id, wallet, amount),
+        
+        ExecuteMsg::ImportUsers { project_id, stage, entries, mode }
+            =>  try_importusers(deps, _env, info, project_id, stage, entries, mode),
+
+        ExecuteMsg::RemoveUser { project_id, stage, wallet }
+            =>  try_removeuser(deps, info, project_id, stage, wallet),
+
+        ExecuteMsg::SetUserAllocation { project_id, stage, wallet, amount }
+            =>  try_setuserallocation(deps, info, project_id, stage, wallet, amount),
+
+        ExecuteMsg::StartVesting { project_id }
+            =>  try_startvesting(deps, _env, info, project_id),
+
+        ExecuteMsg::RetryVestingSync { project_id }
+            =>  try_retryvestingsync(deps, info, project_id),
+
+        ExecuteMsg::SyncVestingUsers { project_id, limit }
+            =>  try_syncvestingusers(deps, info, project_id, limit),
+
+        ExecuteMsg::Contribute { project_id, stage, kyc, referrer }
+            =>  try_contribute(deps, _env, info, project_id, stage, kyc, referrer),
+
+        ExecuteMsg::ApproveMilestone { project_id, milestone_index }
+            =>  try_approvemilestone(deps, info, project_id, milestone_index),
+
+        ExecuteMsg::ReleaseMilestone { project_id, milestone_index }
+            =>  try_releasemilestone(deps, info, project_id, milestone_index),
+
+        ExecuteMsg::SetVotingConfig { quorum, threshold, voting_period }
+            =>  try_setvotingconfig(deps, info, quorum, threshold, voting_period),
+
+        ExecuteMsg::ProposeWindDown { project_id, description }
+            =>  try_proposewinddown(deps, _env, info, project_id, description),
+
+        ExecuteMsg::Vote { project_id, proposal_id, wind_down }
+            =>  try_vote(deps, _env, info, project_id, proposal_id, wind_down),
+
+        ExecuteMsg::EndProposal { project_id, proposal_id }
+            =>  try_endproposal(deps, _env, project_id, proposal_id),
+
+        ExecuteMsg::ClaimRefund { project_id }
+            =>  try_claimrefund(deps, info, project_id),
+
+        ExecuteMsg::CancelProject { project_id }
+            =>  try_cancelproject(deps, info, project_id),
+
+        ExecuteMsg::ArchiveProject { project_id, archived }
+            =>  try_archiveproject(deps, info, project_id, archived),
+
+        ExecuteMsg::Receive(msg)
+            =>  try_receive(deps, info, msg),
+
+        ExecuteMsg::SetMoneyMarket { market_addr, platform_wallet, platform_share, project_share,
+            investor_share }
+            =>  try_setmoneymarket(deps, info, market_addr, platform_wallet, platform_share,
+                project_share, investor_share),
+
+        ExecuteMsg::DepositIdleFunds { project_id, amount }
+            =>  try_depositidlefunds(deps, info, project_id, amount),
+
+        ExecuteMsg::RedeemIdleFunds { project_id, shares }
+            =>  try_redeemidlefunds(deps, info, project_id, shares),
+
+        ExecuteMsg::ClaimYield { project_id, kyc }
+            =>  try_claimyield(deps, _env, info, project_id, kyc),
+
+        ExecuteMsg::SetFeeConfig { collector, raise_fee, token_fee }
+            =>  try_setfeeconfig(deps, info, collector, raise_fee, token_fee),
+
+        ExecuteMsg::SetProjectFees { project_id, fees }
+            =>  try_setprojectfees(deps, info, project_id, fees),
+
+        ExecuteMsg::FinalizeSale { project_id }
+            =>  try_finalizesale(deps, info, project_id),
+
+        ExecuteMsg::SetStageSale { project_id, stage, sale }
+            =>  try_setstagesale(deps, info, project_id, stage, sale),
+
+        ExecuteMsg::FinalizeStage { project_id, stage }
+            =>  try_finalizestage(deps, _env, project_id, stage),
+
+        ExecuteMsg::ClaimAllocationAndRefund { project_id, stage, kyc }
+            =>  try_claimallocationandrefund(deps, _env, info, project_id, stage, kyc),
+
+        ExecuteMsg::PlaceBid { project_id, stage, max_price, kyc }
+            =>  try_placebid(deps, _env, info, project_id, stage, max_price, kyc),
+
+        ExecuteMsg::SettleAuction { project_id, stage, limit }
+            =>  try_settleauction(deps, _env, info, project_id, stage, limit),
+
+        ExecuteMsg::SetLotteryWhitelist { project_id, stage, entries }
+            =>  try_setlotterywhitelist(deps, info, project_id, stage, entries),
+
+        ExecuteMsg::RegisterTickets { project_id, stage, tickets, kyc }
+            =>  try_registertickets(deps, _env, info, project_id, stage, tickets, kyc),
+
+        ExecuteMsg::CommitLotterySeed { project_id, stage, commitment }
+            =>  try_commitlotteryseed(deps, _env, info, project_id, stage, commitment),
+
+        ExecuteMsg::RevealLotterySeed { project_id, stage, seed }
+            =>  try_reveallotteryseed(deps, _env, info, project_id, stage, seed),
+
+        ExecuteMsg::DrawLottery { project_id, stage, limit }
+            =>  try_drawlottery(deps, _env, info, project_id, stage, limit),
+
+        ExecuteMsg::SetStakingContract { staking_addr }
+            =>  try_setstakingcontract(deps, info, staking_addr),
+
+        ExecuteMsg::SetProjectTiers { project_id, tiers }
+            =>  try_setprojecttiers(deps, info, project_id, tiers),
+
+        ExecuteMsg::SetRegistration { project_id, registration }
+            =>  try_setregistration(deps, info, project_id, registration),
+
+        ExecuteMsg::Register { project_id }
+            =>  try_register(deps, _env, info, project_id),
+
+        ExecuteMsg::SetCompliance { compliance }
+            =>  try_setcompliance(deps, info, compliance),
+
+        ExecuteMsg::SetDenied { project_id, wallets, denied }
+            =>  try_setdenied(deps, info, project_id, wallets, denied),
+
+        ExecuteMsg::SetReferralRates { project_id, rates }
+            =>  try_setreferralrates(deps, info, project_id, rates),
+
+        ExecuteMsg::ClaimReferralRewards { project_id, kyc }
+            =>  try_claimreferralrewards(deps, _env, info, project_id, kyc),
+
+        ExecuteMsg::SetBonusRules { project_id, stage, rules }
+            =>  try_setbonusrules(deps, info, project_id, stage, rules),
+
+        ExecuteMsg::ClaimBonus { project_id, kyc }
+            =>  try_claimbonus(deps, _env, info, project_id, kyc),
+
+        ExecuteMsg::SetLiquidityConfig { project_id, liquidity }
+            =>  try_setliquidityconfig(deps, info, project_id, liquidity),
+
+        ExecuteMsg::BootstrapLiquidity { project_id }
+            =>  try_bootstrapliquidity(deps, _env, info, project_id),
+
+        ExecuteMsg::ClaimLpTokens { project_id }
+            =>  try_claimlptokens(deps, _env, info, project_id),
+
+        ExecuteMsg::SetKycConfig { kyc }
+            =>  try_setkycconfig(deps, info, kyc),
+
+        ExecuteMsg::SetKycRevoked { wallets, revoked }
+            =>  try_setkycrevoked(deps, info, wallets, revoked),
+
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_VESTING_REPLY_ID => handle_instantiate_vesting_reply(deps, msg),
+        VESTING_ADD_PROJECT_REPLY_ID | VESTING_SET_PROJECT_INFO_REPLY_ID
+            => handle_vesting_sync_reply(deps, msg),
+        LIQUIDITY_CREATE_PAIR_REPLY_ID => handle_create_pair_reply(deps, env),
+        LIQUIDITY_PROVIDE_REPLY_ID => handle_provide_liquidity_reply(deps, env),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+fn handle_instantiate_vesting_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let project_id = PENDING_VESTING_PROJECT.load(deps.storage)?;
+    PENDING_VESTING_PROJECT.remove(deps.storage);
+
+    let res = msg.result.into_result().map_err(StdError::generic_err)?;
+    let vesting_addr = res.events.iter()
+        .filter(|e| e.ty == "instantiate" || e.ty == "instantiate_contract")
+        .flat_map(|e| e.attributes.iter())
+        .find(|a| a.key == "_contract_address" || a.key == "contract_address")
+        .map(|a| a.value.clone())
+        .ok_or(ContractError::VestingInstantiateFailed {})?;
+    let vesting_addr = check_addr(deps.api, &vesting_addr)?;
+
+    PROJECT_VESTING_ADDRS.save(deps.storage, project_id.u128().into(), &vesting_addr)?;
+
+    let x = load_project(deps.storage, project_id)?;
+    let msg_addproject = vesting_addproject_msg(&vesting_addr, project_id, &x.config)?;
+
+    Ok(Response::new()
+    .add_submessage(vesting_submsg(deps.storage, project_id, msg_addproject,
+        VESTING_ADD_PROJECT_REPLY_ID)?)
+    .add_attribute("action", "Instantiate vesting contract")
+    .add_attribute("project_id", project_id.to_string())
+    .add_attribute("vesting_addr", vesting_addr.to_string()))
+}
+
+fn handle_vesting_sync_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let project_id = PENDING_VESTING_PROJECT.load(deps.storage)?;
+    PENDING_VESTING_PROJECT.remove(deps.storage);
+
+    let mut status = VESTING_SYNC.may_load(deps.storage, project_id.u128().into())?
+        .unwrap_or_default();
+    let state = match msg.result.into_result() {
+        Ok(_) => SyncState::Success,
+        Err(err) => {
+            status.last_error = Some(err);
+            SyncState::Failed
+        }
+    };
+    if msg.id == VESTING_ADD_PROJECT_REPLY_ID {
+        status.add_project = state.clone();
+    } else {
+        status.set_project_info = state.clone();
+    }
+    VESTING_SYNC.save(deps.storage, project_id.u128().into(), &status)?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Vesting sync")
+    .add_attribute("project_id", project_id.to_string())
+    .add_attribute("result", format!("{:?}", state)))
+}
+
+//vesting messages report back to reply so the sync status can be tracked
+fn vesting_submsg(storage: &mut dyn Storage, project_id: Uint128, msg: WasmMsg, reply_id: u64)
+    -> StdResult<SubMsg>
+{
+    PENDING_VESTING_PROJECT.save(storage, &project_id)?;
+    Ok(SubMsg::reply_always(msg, reply_id))
+}
+
+fn load_project(storage: &dyn Storage, project_id: Uint128) -> Result<ProjectInfo, ContractError>
+{
+    PROJECT_INFOS.may_load(storage, project_id.u128().into())?
+        .ok_or(ContractError::ProjectNotFound { project_id })
+}
+
+fn check_addr(api: &dyn Api, addr: &str) -> Result<Addr, ContractError>
+{
+    api.addr_validate(addr).map_err(|_| ContractError::InvalidAddress { address: addr.to_string() })
+}
+
+//vesting contract of the project, falling back to the shared one
+pub fn get_vesting_addr(storage: &dyn Storage, project_id: Uint128) -> StdResult<Option<Addr>>
+{
+    match PROJECT_VESTING_ADDRS.may_load(storage, project_id.u128().into())? {
+        Some(addr) => Ok(Some(addr)),
+        None => VESTING_ADDR.may_load(storage),
+    }
+}
+
+fn vesting_addproject_msg(vesting_addr: &Addr, project_id: Uint128, config: &Config)
+    -> StdResult<WasmMsg>
+{
+    Ok(WasmMsg::Execute {
+        contract_addr: vesting_addr.to_string(),
+            msg: to_binary(&vestingExecuteMsg::AddProject {
+                project_id,
+                admin: config.owner.to_string(),
+                token_addr: config.token_addr.clone(),
+                start_time: config.start_time
+            })?,
+        funds: Vec::new()
+    })
+}
+
+//investors are left out, they are pushed in pages by SyncVestingUsers
+fn vesting_setprojectinfo_msg(vesting_addr: &Addr, project_id: Uint128, mut project_info: ProjectInfo)
+    -> StdResult<WasmMsg>
+{
+    project_info.seed_users = Vec::new();
+    project_info.presale_users = Vec::new();
+    project_info.ido_users = Vec::new();
+
+    Ok(WasmMsg::Execute {
+        contract_addr: vesting_addr.to_string(),
+            msg: to_binary(&vestingExecuteMsg::SetProjectInfo {
+                project_id,
+                project_info
+            })?,
+        funds: Vec::new()
+    })
+}
+
+pub fn try_retryvestingsync(deps: DepsMut, info: MessageInfo, project_id: Uint128)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let vesting_addr = get_vesting_addr(deps.storage, project_id)?
+        .ok_or(ContractError::NotSetVestAddr { })?;
+
+    let status = VESTING_SYNC.may_load(deps.storage, project_id.u128().into())?
+        .unwrap_or_default();
+    let submsg = if status.add_project != SyncState::Success {
+        let msg = vesting_addproject_msg(&vesting_addr, project_id, &x.config)?;
+        vesting_submsg(deps.storage, project_id, msg, VESTING_ADD_PROJECT_REPLY_ID)?
+    } else if status.set_project_info == SyncState::Failed {
+        let msg = vesting_setprojectinfo_msg(&vesting_addr, project_id, x)?;
+        vesting_submsg(deps.storage, project_id, msg, VESTING_SET_PROJECT_INFO_REPLY_ID)?
+    } else {
+        return Err(ContractError::VestingAlreadySynced { });
+    };
+
+    Ok(Response::new()
+    .add_submessage(submsg)
+    .add_attribute("action", "Retry vesting sync"))
+}
+
+pub fn stage_users<'a>(x: &'a ProjectInfo, stage: &str) -> &'a Vec<UserInfo>
+{
+    match stage {
+        "seed" => &x.seed_users,
+        "presale" => &x.presale_users,
+        _ => &x.ido_users,
+    }
+}
+
+pub fn stage_users_mut<'a>(x: &'a mut ProjectInfo, stage: &str) -> &'a mut Vec<UserInfo>
+{
+    match stage {
+        "seed" => &mut x.seed_users,
+        "presale" => &mut x.presale_users,
+        _ => &mut x.ido_users,
+    }
+}
+
+fn vesting_setusers_msg(project_id: Uint128, stage: &str, user_infos: Vec<UserInfo>)
+    -> vestingExecuteMsg
+{
+    match stage {
+        "seed" => vestingExecuteMsg::SetSeedUsers { project_id, user_infos },
+        "presale" => vestingExecuteMsg::SetPresaleUsers { project_id, user_infos },
+        _ => vestingExecuteMsg::SetIDOUsers { project_id, user_infos },
+    }
+}
+
+fn vesting_adduser_msg(project_id: Uint128, stage: &str, wallet: Addr, amount: Uint128)
+    -> vestingExecuteMsg
+{
+    match stage {
+        "seed" => vestingExecuteMsg::AddSeedUser { project_id, wallet, amount },
+        "presale" => vestingExecuteMsg::AddPresaleUser { project_id, wallet, amount },
+        _ => vestingExecuteMsg::AddIDOUser { project_id, wallet, amount },
+    }
+}
+
+pub fn try_syncvestingusers(deps: DepsMut, info: MessageInfo, project_id: Uint128, limit: Option<u32>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let vesting_addr = get_vesting_addr(deps.storage, project_id)?
+        .ok_or(ContractError::NotSetVestAddr { })?;
+    let mut cursor = VESTING_USER_SYNC.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::VestingNotStarted { })?;
+    if cursor.finished {
+        return Err(ContractError::VestingAlreadySynced { });
+    }
+
+    let mut remaining = limit.unwrap_or(DEFAULT_SYNC_LIMIT).min(MAX_SYNC_LIMIT) as usize;
+    let mut msgs: Vec<vestingExecuteMsg> = Vec::new();
+    let mut synced = 0usize;
+    while remaining > 0 && !cursor.finished {
+        let users = stage_users(&x, &cursor.stage);
+        let start = cursor.index as usize;
+        let end = (start + remaining).min(users.len());
+        let page = &users[start..end];
+
+        //the first page replaces the stage on the vesting side, later pages append
+        if start == 0 {
+            msgs.push(vesting_setusers_msg(project_id, &cursor.stage, page.to_vec()));
+        } else {
+            for user in page {
+                msgs.push(vesting_adduser_msg(project_id, &cursor.stage,
+                    user.wallet_address.clone(), user.total_amount));
+            }
+        }
+        remaining -= page.len();
+        synced += page.len();
+        cursor.index = end as u32;
+
+        if end == users.len() {
+            match STAGES.iter().position(|s| *s == cursor.stage) {
+                Some(i) if i + 1 < STAGES.len() => {
+                    cursor.stage = STAGES[i + 1].to_string();
+                    cursor.index = 0;
+                }
+                _ => cursor.finished = true,
+            }
+        }
+    }
+    VESTING_USER_SYNC.save(deps.storage, project_id.u128().into(), &cursor)?;
+
+    let mut wasm_msgs: Vec<WasmMsg> = Vec::new();
+    for msg in msgs {
+        wasm_msgs.push(WasmMsg::Execute {
+            contract_addr: vesting_addr.to_string(),
+            msg: to_binary(&msg)?,
+            funds: Vec::new()
+        });
+    }
+
+    Ok(Response::new()
+    .add_messages(wasm_msgs)
+    .add_attribute("action", "Sync vesting users")
+    .add_attribute("synced", synced.to_string())
+    .add_attribute("finished", cursor.finished.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_contribute(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: Option<String>,
+    kyc: Option<KycProof>, referrer: Option<String>)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    check_kyc(deps.as_ref(), &env, project_id, &info.sender, kyc)?;
+    check_project_open(deps.storage, project_id)?;
+    if SALE_FINALIZED.has(deps.storage, project_id.u128().into()) {
+        return Err(ContractError::SaleFinalized { });
+    }
+    let mut treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
+
+    let amount = match &info.funds[..] {
+        [coin] if coin.denom == treasury.denom && !coin.amount.is_zero() => coin.amount,
+        _ => return Err(ContractError::InvalidFunds { denom: treasury.denom }),
+    };
+    let referrer = match referrer {
+        Some(referrer) => Some(check_referrer(deps.as_ref(), project_id, &info.sender, &referrer)?),
+        None => None,
+    };
+
+    //buying at a stage records the allocation, otherwise it only funds the treasury
+    let mut token_amount = Uint128::zero();
+    let mut emitted: Vec<Event> = Vec::new();
+    let stage = match stage {
+        Some(stage) => Some(check_stage(&stage)?),
+        None => None,
+    };
+    if let Some(stage) = &stage {
+        check_allocations_open(deps.storage, project_id)?;
+        let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+            .ok_or(ContractError::StageNotOnSale { })?;
+        let now = env.block.time.seconds();
+        let price = match sale.sale_type {
+            SaleType::FixedPrice => sale.price,
+            SaleType::Overflow { start_time, end_time, .. } => {
+                let deposit = OVERFLOW_DEPOSITS.may_load(deps.storage,
+                    (project_id.u128().into(), stage.as_str(), &info.sender))?.unwrap_or_default();
+                check_tier_cap(deps.as_ref(), now, project_id, &info.sender, deposit + amount)?;
+                if let Some(referrer) = referrer {
+                    OVERFLOW_REFERRERS.save(deps.storage,
+                        (project_id.u128().into(), stage.as_str(), &info.sender), &referrer)?;
+                }
+                let res = deposit_overflow(deps, env, info.sender.clone(), project_id, stage.clone(),
+                    amount, start_time, end_time)?;
+                return Ok(res.add_event(events::contribution(project_id, Some(stage), &info.sender,
+                    "overflow_deposit", amount, Uint128::zero(), treasury.total_raised)));
+            }
+            SaleType::DutchAuction { start_time, end_time, .. } => {
+                if now < start_time || now >= end_time {
+                    return Err(ContractError::StageNotOpen { });
+                }
+                dutch_auction_price(&sale, now)
+            }
+            SaleType::BatchAuction { .. } | SaleType::Lottery { .. }
+                => return Err(ContractError::InvalidSaleType { }),
+        };
+        token_amount = tokens_for_payment(&StageSale{ price, ..sale.clone() }, amount)?;
+        if token_amount.is_zero() {
+            return Err(ContractError::ContributionTooSmall { });
+        }
+        check_tier_cap(deps.as_ref(), now, project_id, &info.sender, amount)?;
+
+        if let SaleType::DutchAuction { token_cap, .. } = sale.sale_type {
+            let sold = TOKENS_SOLD.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+                .unwrap_or_default() + token_amount;
+            if sold > token_cap {
+                return Err(ContractError::StageSoldOut { });
+            }
+            TOKENS_SOLD.save(deps.storage, (project_id.u128().into(), stage.as_str()), &sold)?;
+        }
+
+        let mut x = load_project(deps.storage, project_id)?;
+        let allocation = record_allocation(deps.storage, &mut x, stage, &info.sender, token_amount, amount, now)?;
+        emitted.push(events::allocation_changed(project_id, stage, &info.sender,
+            allocation - token_amount, allocation, &info.sender));
+        PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
+    }
+
+    treasury.total_raised += amount;
+    TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+
+    add_contribution(deps.storage, project_id, &info.sender, amount)?;
+    if let Some(referrer) = referrer {
+        accrue_referral(deps.storage, project_id, &referrer, amount, token_amount)?;
+    }
+    let kind = if stage.is_some() { "purchase" } else { "treasury" };
+    emitted.push(events::contribution(project_id, stage.as_deref(), &info.sender, kind, amount,
+        token_amount, treasury.total_raised));
+
+    Ok(Response::new()
+    .add_events(emitted)
+    .add_attribute("action", "Contribute")
+    .add_attribute("project_id", project_id.to_string())
+    .add_attribute("amount", amount.to_string())
+    .add_attribute("token_amount", token_amount.to_string()))
+}
+
+pub fn try_setcompliance(deps: DepsMut, info: MessageInfo, compliance: String)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    COMPLIANCE.save(deps.storage, &check_addr(deps.api, &compliance)?)?;
+    Ok(Response::new()
+    .add_attribute("action", "Set compliance")
+    .add_attribute("compliance", compliance))
+}
+
+//platform-wide without a project_id, otherwise for that project only
+pub fn try_setdenied(deps: DepsMut, info: MessageInfo, project_id: Option<Uint128>, wallets: Vec<String>,
+    denied: bool)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let compliance = COMPLIANCE.may_load(deps.storage)?;
+    if info.sender != owner && Some(&info.sender) != compliance.as_ref() {
+        return Err(ContractError::Unauthorized{ });
+    }
+    if let Some(project_id) = project_id {
+        load_project(deps.storage, project_id)?;
+    }
+
+    for wallet in wallets.iter() {
+        let wallet = check_addr(deps.api, wallet)?;
+        match (project_id, denied) {
+            (None, true) => DENY_LIST.save(deps.storage, &wallet, &true)?,
+            (None, false) => DENY_LIST.remove(deps.storage, &wallet),
+            (Some(project_id), true)
+                => PROJECT_DENY_LIST.save(deps.storage, (project_id.u128().into(), &wallet), &true)?,
+            (Some(project_id), false)
+                => PROJECT_DENY_LIST.remove(deps.storage, (project_id.u128().into(), &wallet)),
+        }
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set denied")
+    .add_attribute("denied", denied.to_string())
+    .add_attribute("count", wallets.len().to_string()))
+}
+
+pub fn is_denied(storage: &dyn Storage, project_id: Uint128, wallet: &Addr) -> bool
+{
+    DENY_LIST.has(storage, wallet)
+        || PROJECT_DENY_LIST.has(storage, (project_id.u128().into(), wallet))
+}
+
+fn check_not_denied(storage: &dyn Storage, project_id: Uint128, wallet: &Addr)
+    ->Result<(), ContractError>
+{
+    if is_denied(storage, project_id, wallet) {
+        return Err(ContractError::Denied { wallet: wallet.to_string() });
+    }
+    Ok(())
+}
+
+pub fn try_setreferralrates(deps: DepsMut, info: MessageInfo, project_id: Uint128,
+    rates: Option<ReferralRates>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+
+    match rates {
+        Some(rates) => {
+            if rates.payment_rate > Decimal::one() || rates.token_rate > Decimal::one() {
+                return Err(ContractError::InvalidFee { });
+            }
+            REFERRAL_RATES.save(deps.storage, project_id.u128().into(), &rates)?;
+        }
+        None => REFERRAL_RATES.remove(deps.storage, project_id.u128().into()),
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set referral rates"))
+}
+
+fn check_referrer(deps: Deps, project_id: Uint128, wallet: &Addr, referrer: &str)
+    ->Result<Addr, ContractError>
+{
+    let referrer = check_addr(deps.api, referrer)?;
+    if &referrer == wallet {
+        return Err(ContractError::SelfReferral { });
+    }
+    check_not_denied(deps.storage, project_id, &referrer)?;
+    Ok(referrer)
+}
+
+//credits the referrer with its rates of an accepted payment and the tokens it bought
+fn accrue_referral(storage: &mut dyn Storage, project_id: Uint128, referrer: &Addr, payment: Uint128,
+    tokens: Uint128)
+    ->StdResult<()>
+{
+    let rates = match REFERRAL_RATES.may_load(storage, project_id.u128().into())? {
+        Some(rates) => rates,
+        None => return Ok(()),
+    };
+    let mut rewards = REFERRAL_REWARDS.may_load(storage, (project_id.u128().into(), referrer))?
+        .unwrap_or_default();
+    REFERRER_RANKS.remove(storage,
+        (project_id.u128().into(), rewards.referred.u128().into(), referrer));
+
+    rewards.referred += payment;
+    rewards.funds += payment * rates.payment_rate;
+    rewards.tokens += tokens * rates.token_rate;
+    REFERRAL_REWARDS.save(storage, (project_id.u128().into(), referrer), &rewards)?;
+    REFERRER_RANKS.save(storage,
+        (project_id.u128().into(), rewards.referred.u128().into(), referrer), referrer)?;
+    Ok(())
+}
+
+pub fn try_claimreferralrewards(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128,
+    kyc: Option<KycProof>)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    check_kyc(deps.as_ref(), &env, project_id, &info.sender, kyc)?;
+    check_project_open(deps.storage, project_id)?;
+    let mut rewards = REFERRAL_REWARDS.may_load(deps.storage, (project_id.u128().into(), &info.sender))?
+        .unwrap_or_default();
+    let funds = rewards.funds - rewards.claimed_funds;
+    let tokens = rewards.tokens - rewards.claimed_tokens;
+    if funds.is_zero() && tokens.is_zero() {
+        return Err(ContractError::NoReferralRewards { });
+    }
+    rewards.claimed_funds = rewards.funds;
+    rewards.claimed_tokens = rewards.tokens;
+    REFERRAL_REWARDS.save(deps.storage, (project_id.u128().into(), &info.sender), &rewards)?;
+
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    let mut emitted: Vec<Event> = Vec::new();
+    if !funds.is_zero() {
+        let mut treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+        let position = MARKET_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+            .unwrap_or_default();
+        if idle_funds(&treasury, &position)? < funds {
+            return Err(ContractError::NotEnoughBalance { });
+        }
+        treasury.released += funds;
+        TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+        emitted.push(events::claim(project_id, &info.sender, "referral", &treasury.denom, funds));
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin{ denom: treasury.denom, amount: funds }],
+        }));
+    }
+    if !tokens.is_zero() {
+        let x = load_project(deps.storage, project_id)?;
+        spend_project_tokens(deps.storage, project_id, tokens)?;
+        emitted.push(events::claim(project_id, &info.sender, "referral", &x.config.token_addr, tokens));
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: x.config.token_addr,
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: tokens,
+            })?,
+            funds: Vec::new()
+        }));
+    }
+
+    Ok(Response::new()
+    .add_messages(msgs)
+    .add_events(emitted)
+    .add_attribute("action", "Claim referral rewards")
+    .add_attribute("funds", funds.to_string())
+    .add_attribute("tokens", tokens.to_string()))
+}
+
+pub fn try_setbonusrules(deps: DepsMut, info: MessageInfo, project_id: Uint128, stage: String,
+    rules: Option<BonusRules>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let stage = check_stage(&stage)?;
+
+    match rules {
+        Some(rules) => {
+            if rules.time_tiers.windows(2).any(|w| w[0].until >= w[1].until)
+                || rules.amount_tiers.windows(2).any(|w| w[0].min_payment >= w[1].min_payment) {
+                return Err(ContractError::InvalidTiers { });
+            }
+            BONUS_RULES.save(deps.storage, (project_id.u128().into(), stage.as_str()), &rules)?;
+        }
+        None => BONUS_RULES.remove(deps.storage, (project_id.u128().into(), stage.as_str())),
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set bonus rules")
+    .add_attribute("stage", stage))
+}
+
+//bonus tokens for buying `tokens` with `payment` at `now`
+pub fn stage_bonus(storage: &dyn Storage, project_id: Uint128, stage: &str, now: u64, payment: Uint128,
+    tokens: Uint128)
+    ->StdResult<Uint128>
+{
+    let rules = match BONUS_RULES.may_load(storage, (project_id.u128().into(), stage))? {
+        Some(rules) => rules,
+        None => return Ok(Uint128::zero()),
+    };
+    let time_rate = rules.time_tiers.iter()
+        .find(|t| now < t.until)
+        .map(|t| t.rate)
+        .unwrap_or_else(Decimal::zero);
+    let amount_rate = rules.amount_tiers.iter().rev()
+        .find(|t| t.min_payment <= payment)
+        .map(|t| t.rate)
+        .unwrap_or_else(Decimal::zero);
+    Ok(tokens * time_rate + tokens * amount_rate)
+}
+
+fn check_add_bonus(users: &mut [UserInfo], wallet: &Addr, bonus: Uint128)
+{
+    if let Some(user) = users.iter_mut().find(|x| x.wallet_address == *wallet) {
+        user.bonus_amount += bonus;
+    }
+}
+
+//adds `tokens` bought with `payment` to a stage user with the stage bonus at `now`,
+//returning the new allocation; admin allocations have no payment
+fn record_allocation(storage: &dyn Storage, x: &mut ProjectInfo, stage: &str, wallet: &Addr, tokens: Uint128,
+    payment: Uint128, now: u64)
+    ->StdResult<Uint128>
+{
+    let bonus = stage_bonus(storage, x.project_id, stage, now, payment, tokens)?;
+    let users = stage_users_mut(x, stage);
+    let allocation = check_add_userinfo(users, wallet.clone(), tokens);
+    check_add_bonus(users, wallet, bonus);
+    Ok(allocation)
+}
+
+//bonus of a stage vested at `now`, linear over duration after cliff from the project start
+fn vested_bonus(user: &UserInfo, rules: Option<&BonusRules>, start_time: u64, now: u64) -> Uint128
+{
+    let (cliff, duration) = rules.map(|r| (r.cliff, r.duration)).unwrap_or_default();
+    let elapsed = now.saturating_sub(start_time);
+    if elapsed < cliff {
+        return Uint128::zero();
+    }
+    if elapsed - cliff >= duration {
+        return user.bonus_amount;
+    }
+    user.bonus_amount.multiply_ratio(elapsed - cliff, duration)
+}
+
+pub fn bonus_of(storage: &dyn Storage, x: &ProjectInfo, wallet: &Addr, now: u64) -> StdResult<BonusResponse>
+{
+    let start_time = x.config.start_time.u128() as u64;
+    let mut res = BonusResponse{
+        total: Uint128::zero(),
+        released: Uint128::zero(),
+        claimable: Uint128::zero(),
+    };
+    for stage in STAGES.iter() {
+        let rules = BONUS_RULES.may_load(storage, (x.project_id.u128().into(), *stage))?;
+        for user in stage_users(x, stage).iter().filter(|u| u.wallet_address == *wallet) {
+            res.total += user.bonus_amount;
+            res.released += user.bonus_released;
+            if start_time != 0 {
+                res.claimable += vested_bonus(user, rules.as_ref(), start_time, now)
+                    .saturating_sub(user.bonus_released);
+            }
+        }
+    }
+    Ok(res)
+}
+
+pub fn try_claimbonus(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128,
+    kyc: Option<KycProof>)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    check_kyc(deps.as_ref(), &env, project_id, &info.sender, kyc)?;
+    let mut x = load_project(deps.storage, project_id)?;
+    if x.config.start_time.is_zero() {
+        return Err(ContractError::VestingNotStarted { });
+    }
+    let start_time = x.config.start_time.u128() as u64;
+    let now = env.block.time.seconds();
+
+    let mut amount = Uint128::zero();
+    for stage in STAGES.iter() {
+        let rules = BONUS_RULES.may_load(deps.storage, (project_id.u128().into(), *stage))?;
+        for user in stage_users_mut(&mut x, stage).iter_mut().filter(|u| u.wallet_address == info.sender) {
+            //rules or start time moved later can put the vested amount below the released one
+            let vested = vested_bonus(user, rules.as_ref(), start_time, now);
+            amount += vested.saturating_sub(user.bonus_released);
+            user.bonus_released = user.bonus_released.max(vested);
+        }
+    }
+    if amount.is_zero() {
+        return Err(ContractError::NoBonus { });
+    }
+    spend_project_tokens(deps.storage, project_id, amount)?;
+    PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
+
+    let event = events::claim(project_id, &info.sender, "bonus", &x.config.token_addr, amount);
+    let msg = WasmMsg::Execute {
+        contract_addr: x.config.token_addr,
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: info.sender.to_string(),
+            amount,
+        })?,
+        funds: Vec::new()
+    };
+    Ok(Response::new()
+    .add_message(msg)
+    .add_event(event)
+    .add_attribute("action", "Claim bonus")
+    .add_attribute("amount", amount.to_string()))
+}
+
+pub fn try_setkycconfig(deps: DepsMut, info: MessageInfo, kyc: Option<KycConfig>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    match kyc {
+        Some(kyc) => KYC_CONFIG.save(deps.storage, &kyc)?,
+        None => KYC_CONFIG.remove(deps.storage),
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set KYC config"))
+}
+
+pub fn try_setkycrevoked(deps: DepsMut, info: MessageInfo, wallets: Vec<String>, revoked: bool)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    for wallet in wallets.iter() {
+        let wallet = check_addr(deps.api, wallet)?;
+        if revoked {
+            KYC_REVOKED.save(deps.storage, &wallet, &true)?;
+        } else {
+            KYC_REVOKED.remove(deps.storage, &wallet);
+        }
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set KYC revoked")
+    .add_attribute("revoked", revoked.to_string())
+    .add_attribute("count", wallets.len().to_string()))
+}
+
+//with a KYC provider configured, the wallet needs an unexpired, unrevoked approval for the project
+fn check_kyc(deps: Deps, env: &Env, project_id: Uint128, wallet: &Addr, proof: Option<KycProof>)
+    ->Result<(), ContractError>
+{
+    let kyc = match KYC_CONFIG.may_load(deps.storage)? {
+        Some(kyc) => kyc,
+        None => return Ok(()),
+    };
+    if KYC_REVOKED.has(deps.storage, wallet) {
+        return Err(ContractError::KycRevoked { });
+    }
+    let proof = proof.ok_or(ContractError::KycRequired { })?;
+    if proof.expiry <= env.block.time.seconds() {
+        return Err(ContractError::KycExpired { });
+    }
+
+    let payload = to_binary(&KycPayload{
+        contract: env.contract.address.to_string(),
+        project_id,
+        wallet: wallet.to_string(),
+        expiry: proof.expiry,
+    })?;
+    let verified = match kyc.scheme {
+        KycScheme::Secp256k1 => {
+            let hash = Sha256::digest(payload.as_slice());
+            deps.api.secp256k1_verify(&hash, &proof.signature, &kyc.pubkey)
+        }
+        KycScheme::Ed25519 => deps.api.ed25519_verify(&payload, &proof.signature, &kyc.pubkey),
+    };
+    if !verified.unwrap_or(false) {
+        return Err(ContractError::InvalidKycSignature { });
+    }
+    Ok(())
+}
+
+pub fn try_setstakingcontract(deps: DepsMut, info: MessageInfo, staking_addr: String)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    STAKING_CONTRACT.save(deps.storage, &check_addr(deps.api, &staking_addr)?)?;
+    Ok(Response::new()
+    .add_attribute("action", "Set staking contract")
+    .add_attribute("staking_addr", staking_addr))
+}
+
+pub fn try_setprojecttiers(deps: DepsMut, info: MessageInfo, project_id: Uint128,
+    tiers: Option<ProjectTiers>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+
+    match tiers {
+        Some(tiers) => {
+            if STAKING_CONTRACT.may_load(deps.storage)?.is_none() {
+                return Err(ContractError::NoStakingContract { });
+            }
+            if tiers.tiers.is_empty()
+                || tiers.tiers.windows(2).any(|w| w[0].min_stake >= w[1].min_stake) {
+                return Err(ContractError::InvalidTiers { });
+            }
+            PROJECT_TIERS.save(deps.storage, project_id.u128().into(), &tiers)?;
+        }
+        None => PROJECT_TIERS.remove(deps.storage, project_id.u128().into()),
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set project tiers"))
+}
+
+pub fn try_setregistration(deps: DepsMut, info: MessageInfo, project_id: Uint128,
+    registration: Option<RegistrationConfig>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+
+    match registration {
+        Some(registration) => {
+            if !PROJECT_TIERS.has(deps.storage, project_id.u128().into()) {
+                return Err(ContractError::NoTiers { });
+            }
+            if registration.start_time >= registration.end_time {
+                return Err(ContractError::InvalidStageWindow { });
+            }
+            REGISTRATIONS.save(deps.storage, project_id.u128().into(), &registration)?;
+        }
+        None => REGISTRATIONS.remove(deps.storage, project_id.u128().into()),
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set registration"))
+}
+
+//records the wallet's current stake and tier weight for the project registration
+pub fn try_register(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    let registration = REGISTRATIONS.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::RegistrationClosed { })?;
+    let now = env.block.time.seconds();
+    if now < registration.start_time || now >= registration.end_time {
+        return Err(ContractError::RegistrationClosed { });
+    }
+    if REGISTRANTS.has(deps.storage, (project_id.u128().into(), &info.sender)) {
+        return Err(ContractError::AlreadyRegistered { });
+    }
+
+    let tiers = PROJECT_TIERS.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTiers { })?;
+    let stake = query_stake(deps.as_ref(), &info.sender, None)?;
+    let weight = match tier_for_stake(&tiers, stake) {
+        Some(tier) if !tier.weight.is_zero() => tier.weight,
+        _ => return Err(ContractError::NotEligible { }),
+    };
+
+    REGISTRANTS.save(deps.storage, (project_id.u128().into(), &info.sender),
+        &Registrant{ stake, weight })?;
+    REGISTERED_WEIGHT.update(deps.storage, project_id.u128().into(),
+        |v| -> StdResult<_> {
+            let (total, count) = v.unwrap_or_default();
+            Ok((total + weight, count + 1))
+        })?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Register")
+    .add_attribute("stake", stake.to_string())
+    .add_attribute("weight", weight.to_string()))
+}
+
+fn query_stake(deps: Deps, wallet: &Addr, height: Option<u64>) -> StdResult<Uint128>
+{
+    let staking_addr = STAKING_CONTRACT.load(deps.storage)?;
+    let res: StakedResponse = deps.querier.query_wasm_smart(staking_addr,
+        &stakingQueryMsg::StakedAt {
+            address: wallet.to_string(),
+            height,
+        })?;
+    Ok(res.stake)
+}
+
+fn tier_for_stake(tiers: &ProjectTiers, stake: Uint128) -> Option<&Tier>
+{
+    tiers.tiers.iter().rev().find(|t| t.min_stake <= stake)
+}
+
+//Payment cap of a wallet, None if the project has no tiers. With a registration
+//the cap is its weight share of total_allocation once registration closes,
+//otherwise the tier allocation of its stake at the project snapshot.
+pub fn tier_allocation(deps: Deps, now: u64, project_id: Uint128, wallet: &Addr)
+    ->StdResult<Option<TierAllocationResponse>>
+{
+    let contributed = CONTRIBUTIONS.may_load(deps.storage, (project_id.u128().into(), wallet))?
+        .unwrap_or_default();
+
+    if let Some(registration) = REGISTRATIONS.may_load(deps.storage, project_id.u128().into())? {
+        let registrant = REGISTRANTS.may_load(deps.storage, (project_id.u128().into(), wallet))?;
+        let (stake, weight) = registrant.map(|r| (r.stake, r.weight)).unwrap_or_default();
+        let (total_weight, _) = REGISTERED_WEIGHT.may_load(deps.storage, project_id.u128().into())?
+            .unwrap_or_default();
+        let cap = if now < registration.end_time || total_weight.is_zero() {
+            Uint128::zero()
+        } else {
+            registration.total_allocation.multiply_ratio(weight, total_weight)
+        };
+        return Ok(Some(TierAllocationResponse{ stake, weight, cap, contributed }));
+    }
+
+    let tiers = match PROJECT_TIERS.may_load(deps.storage, project_id.u128().into())? {
+        Some(tiers) => tiers,
+        None => return Ok(None),
+    };
+    let stake = query_stake(deps, wallet, Some(tiers.snapshot_height))?;
+    let (weight, cap) = match tier_for_stake(&tiers, stake) {
+        Some(tier) => (tier.weight, match &tier.allocation {
+            TierAllocation::Guaranteed { amount } => *amount,
+            TierAllocation::Multiplier { multiplier } => tiers.base_allocation * *multiplier,
+        }),
+        None => (Uint128::zero(), Uint128::zero()),
+    };
+    Ok(Some(TierAllocationResponse{ stake, weight, cap, contributed }))
+}
+
+//`amount` on top of what the wallet already contributed must fit its tier
+fn check_tier_cap(deps: Deps, now: u64, project_id: Uint128, wallet: &Addr, amount: Uint128)
+    ->Result<(), ContractError>
+{
+    if let Some(allocation) = tier_allocation(deps, now, project_id, wallet)? {
+        if allocation.contributed + amount > allocation.cap {
+            return Err(ContractError::AllocationExceeded { });
+        }
+    }
+    Ok(())
+}
+
+pub fn new_auction_state() -> AuctionState
+{
+    AuctionState {
+        phase: AuctionPhase::Bidding,
+        bid_count: 0,
+        cursor: None,
+        group_price: Decimal::zero(),
+        demand_before: Uint128::zero(),
+        demand: Uint128::zero(),
+        clearing_price: Decimal::zero(),
+        marginal_price: None,
+        marginal_fill: Uint128::zero(),
+        marginal_demand: Uint128::zero(),
+    }
+}
+
+pub fn try_placebid(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: String,
+    max_price: Decimal, kyc: Option<KycProof>)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    check_kyc(deps.as_ref(), &env, project_id, &info.sender, kyc)?;
+    check_project_open(deps.storage, project_id)?;
+    check_allocations_open(deps.storage, project_id)?;
+    let stage = check_stage(&stage)?;
+    let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .ok_or(ContractError::StageNotOnSale { })?;
+    let (start_time, end_time) = match sale.sale_type {
+        SaleType::BatchAuction { start_time, end_time, .. } => (start_time, end_time),
+        _ => return Err(ContractError::InvalidSaleType { }),
+    };
+    let now = env.block.time.seconds();
+    if now < start_time || now >= end_time {
+        return Err(ContractError::StageNotOpen { });
+    }
+    if max_price < sale.price {
+        return Err(ContractError::InvalidPrice { });
+    }
+
+    let treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
+    let amount = match &info.funds[..] {
+        [coin] if coin.denom == treasury.denom && !coin.amount.is_zero() => coin.amount,
+        _ => return Err(ContractError::InvalidFunds { denom: treasury.denom }),
+    };
+    let key = (project_id.u128().into(), stage.as_str(), &info.sender);
+    let escrowed = BIDDER_TOTALS.may_load(deps.storage, key.clone())?.unwrap_or_default() + amount;
+    check_tier_cap(deps.as_ref(), now, project_id, &info.sender, escrowed)?;
+    BIDDER_TOTALS.save(deps.storage, key, &escrowed)?;
+
+    let mut state = AUCTION_STATES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .unwrap_or_else(new_auction_state);
+    state.bid_count += 1;
+    let bid = Bid {
+        id: state.bid_count,
+        bidder: info.sender,
+        amount,
+        max_price,
+    };
+    BIDS.save(deps.storage,
+        (project_id.u128().into(), stage.as_str(), (max_price.numerator().into(), bid.id.into())), &bid)?;
+    AUCTION_STATES.save(deps.storage, (project_id.u128().into(), stage.as_str()), &state)?;
+
+    Ok(Response::new()
+    .add_event(events::contribution(project_id, Some(&stage), &bid.bidder, "bid", amount,
+        Uint128::zero(), treasury.total_raised))
+    .add_attribute("action", "Place bid")
+    .add_attribute("bid_id", bid.id.to_string())
+    .add_attribute("amount", amount.to_string())
+    .add_attribute("max_price", max_price.to_string()))
+}
+
+//lowest price at which `payment` buys no more than `tokens`, rounded up
+fn price_for_tokens(sale: &StageSale, payment: Uint128, tokens: Uint128) -> StdResult<Decimal>
+{
+    let ten = Uint256::from(10u8);
+    let numerator = Uint256::from(payment)
+        * ten.pow(sale.token_decimals as u32)
+        * Uint256::from(Decimal::one().denominator());
+    let denominator = Uint256::from(tokens) * ten.pow(sale.payment_decimals as u32);
+    let atomics = (numerator + denominator - Uint256::from(1u8)) / denominator;
+    let atomics = Uint128::try_from(atomics)
+        .map_err(|_| StdError::generic_err("Price overflow"))?;
+    Ok(Decimal::from_ratio(atomics, Decimal::one().denominator()))
+}
+
+//once the bids down to group_price ask for the whole supply, fix the clearing price
+fn check_marginal(state: &mut AuctionState, sale: &StageSale, supply: Uint128) -> StdResult<bool>
+{
+    let group = StageSale{ price: state.group_price, ..sale.clone() };
+    if state.demand.is_zero() || tokens_for_payment(&group, state.demand)? < supply {
+        return Ok(false);
+    }
+
+    state.marginal_price = Some(state.group_price);
+    let price = if state.demand_before.is_zero() {
+        state.group_price
+    } else {
+        price_for_tokens(sale, state.demand_before, supply)?
+    };
+    if price > state.group_price {
+        //bids above the group already take the supply, the group is refunded
+        state.clearing_price = price;
+        state.marginal_fill = Uint128::zero();
+        state.marginal_demand = Uint128::zero();
+    } else {
+        state.clearing_price = state.group_price;
+        state.marginal_fill = supply - tokens_for_payment(&group, state.demand_before)?;
+        state.marginal_demand = tokens_for_payment(&group, state.demand - state.demand_before)?;
+    }
+    Ok(true)
+}
+
+pub fn try_settleauction(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: String,
+    limit: Option<u32>)
+    ->Result<Response, ContractError>
+{
+    check_allocations_open(deps.storage, project_id)?;
+    let stage = check_stage(&stage)?;
+    let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .ok_or(ContractError::StageNotOnSale { })?;
+    let (end_time, supply) = match sale.sale_type {
+        SaleType::BatchAuction { end_time, token_cap, .. } => (end_time, token_cap),
+        _ => return Err(ContractError::InvalidSaleType { }),
+    };
+    if env.block.time.seconds() < end_time {
+        return Err(ContractError::StageNotEnded { });
+    }
+
+    let mut state = AUCTION_STATES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .unwrap_or_else(new_auction_state);
+    if state.phase == AuctionPhase::Settled {
+        return Err(ContractError::AuctionSettled { });
+    }
+    if state.phase == AuctionPhase::Bidding {
+        state.phase = AuctionPhase::Pricing;
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_SYNC_LIMIT).min(MAX_SYNC_LIMIT) as usize;
+    let max = state.cursor.clone().map(|c| Bound::exclusive(c.to_vec()));
+    let bids: Vec<(Vec<u8>, Bid)> = BIDS.prefix((project_id.u128().into(), stage.as_str()))
+        .range(deps.storage, None, max, Order::Descending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    let exhausted = bids.len() < limit;
+
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    let mut emitted: Vec<Event> = Vec::new();
+    if state.phase == AuctionPhase::Pricing {
+        let mut priced = false;
+        for (key, bid) in bids {
+            if !state.demand.is_zero() && bid.max_price < state.group_price {
+                if check_marginal(&mut state, &sale, supply)? {
+                    priced = true;
+                    break;
+                }
+                state.demand_before = state.demand;
+            }
+            state.group_price = bid.max_price;
+            state.demand += bid.amount;
+            state.cursor = Some(Binary::from(key));
+        }
+        if !priced && exhausted && !check_marginal(&mut state, &sale, supply)? {
+            //undersubscribed, every bid is filled at the reserve price
+            state.clearing_price = sale.price;
+            state.marginal_price = None;
+            priced = true;
+        }
+        if priced {
+            state.phase = AuctionPhase::Filling;
+            state.cursor = None;
+        }
+    } else {
+        let clearing = StageSale{ price: state.clearing_price, ..sale.clone() };
+        let mut x = load_project(deps.storage, project_id)?;
+        let mut treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+        for (key, bid) in bids {
+            let accepted = match state.marginal_price {
+                Some(marginal) if bid.max_price < marginal => Uint128::zero(),
+                Some(marginal) if bid.max_price == marginal => {
+                    if state.marginal_demand.is_zero() {
+                        Uint128::zero()
+                    } else {
+                        bid.amount.multiply_ratio(state.marginal_fill, state.marginal_demand)
+                    }
+                }
+                _ => bid.amount,
+            };
+            let token_amount = tokens_for_payment(&clearing, accepted)?;
+            if !token_amount.is_zero() {
+                let allocation = record_allocation(deps.storage, &mut x, &stage, &bid.bidder, token_amount,
+                    accepted, env.block.time.seconds())?;
+                emitted.push(events::allocation_changed(project_id, &stage, &bid.bidder,
+                    allocation - token_amount, allocation, &info.sender));
+                treasury.total_raised += accepted;
+                add_contribution(deps.storage, project_id, &bid.bidder, accepted)?;
+            }
+            let refund = bid.amount - accepted;
+            if !refund.is_zero() {
+                msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: bid.bidder.to_string(),
+                    amount: vec![Coin{ denom: treasury.denom.clone(), amount: refund }],
+                }));
+                emitted.push(events::claim(project_id, &bid.bidder, "escrow_refund", &treasury.denom, refund));
+            }
+            state.cursor = Some(Binary::from(key));
+        }
+        PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
+        TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+        if exhausted {
+            state.phase = AuctionPhase::Settled;
+        }
+    }
+    AUCTION_STATES.save(deps.storage, (project_id.u128().into(), stage.as_str()), &state)?;
+
+    Ok(Response::new()
+    .add_messages(msgs)
+    .add_events(emitted)
+    .add_attribute("action", "Settle auction")
+    .add_attribute("phase", format!("{:?}", state.phase))
+    .add_attribute("clearing_price", state.clearing_price.to_string()))
+}
+
+pub fn try_setlotterywhitelist(deps: DepsMut, info: MessageInfo, project_id: Uint128, stage: String,
+    entries: Vec<WhitelistEntry>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let stage = check_stage(&stage)?;
+
+    for entry in entries.iter() {
+        let wallet = check_addr(deps.api, &entry.wallet)?;
+        check_not_denied(deps.storage, project_id, &wallet)?;
+        let key = (project_id.u128().into(), stage.as_str(), &wallet);
+        let mut lottery_entry = LOTTERY_ENTRIES.may_load(deps.storage, key.clone())?
+            .unwrap_or_default();
+        if entry.max_tickets < lottery_entry.tickets {
+            return Err(ContractError::TooManyTickets { });
+        }
+        lottery_entry.max_tickets = entry.max_tickets;
+        LOTTERY_ENTRIES.save(deps.storage, key, &lottery_entry)?;
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set lottery whitelist")
+    .add_attribute("count", entries.len().to_string()))
+}
+
+pub fn try_registertickets(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: String,
+    tickets: u32, kyc: Option<KycProof>)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    check_kyc(deps.as_ref(), &env, project_id, &info.sender, kyc)?;
+    check_project_open(deps.storage, project_id)?;
+    check_allocations_open(deps.storage, project_id)?;
+    let stage = check_stage(&stage)?;
+    let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .ok_or(ContractError::StageNotOnSale { })?;
+    let (ticket_price, start_time, end_time) = match sale.sale_type {
+        SaleType::Lottery { ticket_price, start_time, end_time, .. } => (ticket_price, start_time, end_time),
+        _ => return Err(ContractError::InvalidSaleType { }),
+    };
+    let now = env.block.time.seconds();
+    if now < start_time || now >= end_time {
+        return Err(ContractError::StageNotOpen { });
+    }
+
+    let key = (project_id.u128().into(), stage.as_str(), &info.sender);
+    let mut entry = LOTTERY_ENTRIES.may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NotWhitelisted { })?;
+    if tickets == 0 || entry.tickets + tickets > entry.max_tickets {
+        return Err(ContractError::TooManyTickets { });
+    }
+
+    let treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
+    let cost = ticket_price * Uint128::from(tickets);
+    match &info.funds[..] {
+        [coin] if coin.denom == treasury.denom && coin.amount == cost => {}
+        _ => return Err(ContractError::InvalidFunds { denom: treasury.denom }),
+    }
+    check_tier_cap(deps.as_ref(), now, project_id, &info.sender,
+        ticket_price * Uint128::from(entry.tickets + tickets))?;
+
+    let mut lottery = LOTTERIES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .unwrap_or_default();
+    TICKET_RANGES.save(deps.storage,
+        (project_id.u128().into(), stage.as_str(), lottery.ticket_count.into()), &info.sender)?;
+    let first_ticket = lottery.ticket_count;
+    lottery.ticket_count += tickets as u64;
+    LOTTERIES.save(deps.storage, (project_id.u128().into(), stage.as_str()), &lottery)?;
+
+    entry.tickets += tickets;
+    LOTTERY_ENTRIES.save(deps.storage, key, &entry)?;
+
+    Ok(Response::new()
+    .add_event(events::contribution(project_id, Some(&stage), &info.sender, "tickets", cost,
+        Uint128::zero(), treasury.total_raised))
+    .add_attribute("action", "Register tickets")
+    .add_attribute("first_ticket", first_ticket.to_string())
+    .add_attribute("tickets", tickets.to_string()))
+}
+
+pub fn try_commitlotteryseed(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: String,
+    commitment: Binary)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let stage = check_stage(&stage)?;
+    let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .ok_or(ContractError::StageNotOnSale { })?;
+    let end_time = match sale.sale_type {
+        SaleType::Lottery { end_time, .. } => end_time,
+        _ => return Err(ContractError::InvalidSaleType { }),
+    };
+    if env.block.time.seconds() >= end_time {
+        return Err(ContractError::StageNotOpen { });
+    }
+    if commitment.len() != 32 {
+        return Err(ContractError::InvalidCommitment { });
+    }
+
+    let mut lottery = LOTTERIES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .unwrap_or_default();
+    if lottery.commitment.is_some() {
+        return Err(ContractError::SeedAlreadyCommitted { });
+    }
+    lottery.commitment = Some(commitment.clone());
+    LOTTERIES.save(deps.storage, (project_id.u128().into(), stage.as_str()), &lottery)?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Commit lottery seed")
+    .add_attribute("commitment", commitment.to_base64()))
+}
+
+pub fn try_reveallotteryseed(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: String,
+    seed: Binary)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let stage = check_stage(&stage)?;
+    let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .ok_or(ContractError::StageNotOnSale { })?;
+    let end_time = match sale.sale_type {
+        SaleType::Lottery { end_time, .. } => end_time,
+        _ => return Err(ContractError::InvalidSaleType { }),
+    };
+    if env.block.time.seconds() < end_time {
+        return Err(ContractError::StageNotEnded { });
+    }
+    if env.block.time.seconds() >= end_time + LOTTERY_REVEAL_PERIOD {
+        return Err(ContractError::RevealDeadlinePassed { });
+    }
+
+    let mut lottery = LOTTERIES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .unwrap_or_default();
+    let commitment = lottery.commitment.clone().ok_or(ContractError::SeedNotCommitted { })?;
+    if lottery.randomness.is_some() {
+        return Err(ContractError::SeedAlreadyRevealed { });
+    }
+    if Sha256::digest(seed.as_slice()).as_slice() != commitment.as_slice() {
+        return Err(ContractError::InvalidSeed { });
+    }
+
+    let randomness = Sha256::new()
+        .chain(seed.as_slice())
+        .chain(env.block.height.to_be_bytes())
+        .chain(env.block.time.nanos().to_be_bytes())
+        .finalize();
+    let randomness = Binary::from(randomness.as_slice());
+    lottery.randomness = Some(randomness.clone());
+    LOTTERIES.save(deps.storage, (project_id.u128().into(), stage.as_str()), &lottery)?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Reveal lottery seed")
+    .add_attribute("height", env.block.height.to_string())
+    .add_attribute("time", env.block.time.nanos().to_string())
+    .add_attribute("randomness", randomness.to_base64()))
+}
+
+//Draws are a partial Fisher-Yates shuffle over ticket numbers 0..ticket_count.
+//Draw i picks position j = i + r % (ticket_count - i), where r is the first 8 bytes
+//(big endian) of sha256(randomness | i as 8 big endian bytes); the ticket at j wins
+//and the ticket at i moves to j.
+pub fn try_drawlottery(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: String,
+    limit: Option<u32>)
+    ->Result<Response, ContractError>
+{
+    check_allocations_open(deps.storage, project_id)?;
+    let stage = check_stage(&stage)?;
+    let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .ok_or(ContractError::StageNotOnSale { })?;
+    let (ticket_price, winning_tickets) = match sale.sale_type {
+        SaleType::Lottery { ticket_price, winning_tickets, .. } => (ticket_price, winning_tickets),
+        _ => return Err(ContractError::InvalidSaleType { }),
+    };
+    let mut lottery = LOTTERIES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .unwrap_or_default();
+    let randomness = lottery.randomness.clone().ok_or(ContractError::SeedNotRevealed { })?;
+    let total = winning_tickets.min(lottery.ticket_count);
+    if lottery.drawn >= total {
+        return Err(ContractError::StageFinalized { });
+    }
+
+    let tokens_per_ticket = tokens_for_payment(&sale, ticket_price)?;
+    let mut x = load_project(deps.storage, project_id)?;
+    let mut treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+    let limit = limit.unwrap_or(DEFAULT_SYNC_LIMIT).min(MAX_SYNC_LIMIT) as u64;
+    let end = total.min(lottery.drawn + limit);
+
+    let mut emitted: Vec<Event> = Vec::new();
+    for i in lottery.drawn..end {
+        let hash = Sha256::new()
+            .chain(randomness.as_slice())
+            .chain(i.to_be_bytes())
+            .finalize();
+        let mut r = [0u8; 8];
+        r.copy_from_slice(&hash[..8]);
+        let j = i + u64::from_be_bytes(r) % (lottery.ticket_count - i);
+
+        let swap_key = |n: u64| (project_id.u128().into(), stage.as_str(), U64Key::from(n));
+        let ticket = LOTTERY_SWAPS.may_load(deps.storage, swap_key(j))?.unwrap_or(j);
+        let moved = LOTTERY_SWAPS.may_load(deps.storage, swap_key(i))?.unwrap_or(i);
+        LOTTERY_SWAPS.save(deps.storage, swap_key(j), &moved)?;
+
+        let (_, winner) = TICKET_RANGES.prefix((project_id.u128().into(), stage.as_str()))
+            .range(deps.storage, None, Some(Bound::inclusive_int(ticket)), Order::Descending)
+            .next()
+            .ok_or_else(|| StdError::not_found("ticket"))??;
+
+        let allocation = record_allocation(deps.storage, &mut x, &stage, &winner, tokens_per_ticket,
+            ticket_price, env.block.time.seconds())?;
+        emitted.push(events::allocation_changed(project_id, &stage, &winner,
+            allocation - tokens_per_ticket, allocation, &info.sender));
+        treasury.total_raised += ticket_price;
+        add_contribution(deps.storage, project_id, &winner, ticket_price)?;
+        LOTTERY_ENTRIES.update(deps.storage, (project_id.u128().into(), stage.as_str(), &winner),
+            |v| -> StdResult<_> {
+                let mut entry = v.unwrap_or_default();
+                entry.won += 1;
+                Ok(entry)
+            })?;
+    }
+    lottery.drawn = end;
+    PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
+    TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+    LOTTERIES.save(deps.storage, (project_id.u128().into(), stage.as_str()), &lottery)?;
+
+    Ok(Response::new()
+    .add_events(emitted)
+    .add_attribute("action", "Draw lottery")
+    .add_attribute("drawn", lottery.drawn.to_string())
+    .add_attribute("total", total.to_string()))
+}
+
+//refund of the tickets that did not win once the draw is done,
+//or of all tickets when the seed was not revealed by the deadline
+fn claim_lottery_refund(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: &str,
+    ticket_price: Uint128)
+    ->Result<Response, ContractError>
+{
+    let sale = STAGE_SALES.load(deps.storage, (project_id.u128().into(), stage))?;
+    let (end_time, winning_tickets) = match sale.sale_type {
+        SaleType::Lottery { end_time, winning_tickets, .. } => (end_time, winning_tickets),
+        _ => return Err(ContractError::InvalidSaleType { }),
+    };
+    let lottery = LOTTERIES.may_load(deps.storage, (project_id.u128().into(), stage))?
+        .unwrap_or_default();
+    let expired = lottery.randomness.is_none()
+        && env.block.time.seconds() >= end_time + LOTTERY_REVEAL_PERIOD;
+    if !expired && (lottery.randomness.is_none() || lottery.drawn < winning_tickets.min(lottery.ticket_count)) {
+        return Err(ContractError::LotteryNotDrawn { });
+    }
+
+    let key = (project_id.u128().into(), stage, &info.sender);
+    let mut entry: LotteryEntry = LOTTERY_ENTRIES.may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    if entry.tickets == 0 {
+        return Err(ContractError::NoDeposit { });
+    }
+    if entry.refunded {
+        return Err(ContractError::RefundAlreadyClaimed { });
+    }
+    entry.refunded = true;
+    LOTTERY_ENTRIES.save(deps.storage, key, &entry)?;
+
+    let refund = ticket_price * Uint128::from(entry.tickets - entry.won);
+    let treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+    let mut res = Response::new();
+    if !refund.is_zero() {
+        res = res.add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin{ denom: treasury.denom.clone(), amount: refund }],
+        }))
+        .add_event(events::claim(project_id, &info.sender, "escrow_refund", &treasury.denom, refund));
+    }
+
+    Ok(res
+    .add_attribute("action", "Claim lottery refund")
+    .add_attribute("won", entry.won.to_string())
+    .add_attribute("refund", refund.to_string()))
+}
+
+//price of a dutch auction stage at `now`, the stage price is the floor
+pub fn dutch_auction_price(sale: &StageSale, now: u64) -> Decimal
+{
+    match sale.sale_type {
+        SaleType::DutchAuction { start_price, start_time, end_time, .. } => {
+            if now <= start_time {
+                return start_price;
+            }
+            if now >= end_time {
+                return sale.price;
+            }
+            let spread = start_price - sale.price;
+            let drop = Uint128::from(spread.numerator())
+                .multiply_ratio(now - start_time, end_time - start_time);
+            start_price - Decimal::from_ratio(drop, spread.denominator())
+        }
+        _ => sale.price,
+    }
+}
+
+//overflow deposits are escrowed until the stage is finalized
+#[allow(clippy::too_many_arguments)]
+fn deposit_overflow(deps: DepsMut, env: Env, wallet: Addr, project_id: Uint128, stage: String,
+    amount: Uint128, start_time: u64, end_time: u64)
+    ->Result<Response, ContractError>
+{
+    let now = env.block.time.seconds();
+    if now < start_time || now >= end_time {
+        return Err(ContractError::StageNotOpen { });
+    }
+
+    OVERFLOW_DEPOSITS.update(deps.storage, (project_id.u128().into(), stage.as_str(), &wallet),
+        |v| -> StdResult<_> { Ok(v.unwrap_or_default() + amount) })?;
+    OVERFLOW_STATUS.update(deps.storage, (project_id.u128().into(), stage.as_str()),
+        |v| -> StdResult<_> {
+            let mut status = v.unwrap_or_default();
+            status.total_deposits += amount;
+            Ok(status)
+        })?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Deposit")
+    .add_attribute("project_id", project_id.to_string())
+    .add_attribute("stage", stage)
+    .add_attribute("amount", amount.to_string()))
+}
+
+pub fn try_finalizestage(deps: DepsMut, env: Env, project_id: Uint128, stage: String)
+    ->Result<Response, ContractError>
+{
+    let stage = check_stage(&stage)?;
+    let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .ok_or(ContractError::StageNotOnSale { })?;
+    let end_time = match sale.sale_type {
+        SaleType::Overflow { end_time, .. } => end_time,
+        _ => return Err(ContractError::StageNotOnSale { }),
+    };
+    if env.block.time.seconds() < end_time {
+        return Err(ContractError::StageNotEnded { });
+    }
+
+    let mut status = OVERFLOW_STATUS.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .unwrap_or_default();
+    if status.finalized {
+        return Err(ContractError::StageFinalized { });
+    }
+    status.finalized = true;
+    OVERFLOW_STATUS.save(deps.storage, (project_id.u128().into(), stage.as_str()), &status)?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Finalize stage")
+    .add_attribute("stage", stage)
+    .add_attribute("total_deposits", status.total_deposits.to_string()))
+}
+
+pub fn try_claimallocationandrefund(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128,
+    stage: String, kyc: Option<KycProof>)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    check_kyc(deps.as_ref(), &env, project_id, &info.sender, kyc)?;
+    let stage = check_stage(&stage)?;
+    let sale = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .ok_or(ContractError::StageNotOnSale { })?;
+    let cap = match sale.sale_type {
+        SaleType::Overflow { cap, .. } => cap,
+        SaleType::Lottery { ticket_price, .. }
+            => return claim_lottery_refund(deps, env, info, project_id, &stage, ticket_price),
+        _ => return Err(ContractError::StageNotOnSale { }),
+    };
+    check_allocations_open(deps.storage, project_id)?;
+    let status = OVERFLOW_STATUS.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+        .unwrap_or_default();
+    if !status.finalized {
+        return Err(ContractError::StageNotFinalized { });
+    }
+
+    let key = (project_id.u128().into(), stage.as_str(), &info.sender);
+    let deposit = OVERFLOW_DEPOSITS.may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NoDeposit { })?;
+    OVERFLOW_DEPOSITS.remove(deps.storage, key);
+
+    //min(deposit, deposit * cap / total_deposits), the rest is refunded
+    let accepted = if status.total_deposits > cap {
+        deposit.multiply_ratio(cap, status.total_deposits)
+    } else {
+        deposit
+    };
+    let refund = deposit - accepted;
+    let token_amount = tokens_for_payment(&sale, accepted)?;
+
+    let mut x = load_project(deps.storage, project_id)?;
+    let allocation = record_allocation(deps.storage, &mut x, &stage, &info.sender, token_amount, accepted,
+        env.block.time.seconds())?;
+    PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
+    let mut res = Response::new()
+        .add_event(events::allocation_changed(project_id, &stage, &info.sender,
+            allocation - token_amount, allocation, &info.sender));
+
+    let mut treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+    treasury.total_raised += accepted;
+    TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+    add_contribution(deps.storage, project_id, &info.sender, accepted)?;
+
+    let key = (project_id.u128().into(), stage.as_str(), &info.sender);
+    if let Some(referrer) = OVERFLOW_REFERRERS.may_load(deps.storage, key.clone())? {
+        OVERFLOW_REFERRERS.remove(deps.storage, key);
+        accrue_referral(deps.storage, project_id, &referrer, accepted, token_amount)?;
+    }
+
+    if !refund.is_zero() {
+        res = res.add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin{ denom: treasury.denom.clone(), amount: refund }],
+        }))
+        .add_event(events::claim(project_id, &info.sender, "escrow_refund", &treasury.denom, refund));
+    }
+
+    Ok(res
+    .add_attribute("action", "Claim allocation and refund")
+    .add_attribute("accepted", accepted.to_string())
+    .add_attribute("token_amount", token_amount.to_string())
+    .add_attribute("refund", refund.to_string()))
+}
+
+pub fn check_stage(stage: &str) -> Result<String, ContractError>
+{
+    let stage = stage.to_lowercase();
+    if !STAGES.contains(&stage.as_str()) {
+        return Err(ContractError::InvalidStage { stage });
+    }
+    Ok(stage)
+}
+
+pub fn try_setstagesale(deps: DepsMut, info: MessageInfo, project_id: Uint128, stage: String,
+    sale: StageSale)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let stage = check_stage(&stage)?;
+    if sale.price.is_zero() || sale.payment_decimals > 18 || sale.token_decimals > 18 {
+        return Err(ContractError::InvalidPrice { });
+    }
+    match sale.sale_type {
+        SaleType::Overflow { start_time, end_time, .. } |
+        SaleType::BatchAuction { start_time, end_time, .. } if start_time >= end_time
+            => return Err(ContractError::InvalidStageWindow { }),
+        SaleType::DutchAuction { start_price, start_time, end_time, .. } => {
+            if start_time >= end_time {
+                return Err(ContractError::InvalidStageWindow { });
+            }
+            if start_price < sale.price {
+                return Err(ContractError::InvalidPrice { });
+            }
+        }
+        SaleType::Lottery { ticket_price, start_time, end_time, winning_tickets } => {
+            if start_time >= end_time {
+                return Err(ContractError::InvalidStageWindow { });
+            }
+            if winning_tickets == 0 || tokens_for_payment(&sale, ticket_price)?.is_zero() {
+                return Err(ContractError::InvalidPrice { });
+            }
+        }
+        _ => {}
+    }
+    //escrowed payments are settled by the sale type they were made under
+    let key = (project_id.u128().into(), stage.as_str());
+    let deposits = OVERFLOW_STATUS.may_load(deps.storage, key.clone())?.unwrap_or_default().total_deposits;
+    let bids = AUCTION_STATES.may_load(deps.storage, key.clone())?.map_or(0, |state| state.bid_count);
+    let tickets = LOTTERIES.may_load(deps.storage, key.clone())?.unwrap_or_default().ticket_count;
+    if !deposits.is_zero() || bids > 0 || tickets > 0 {
+        return Err(ContractError::StageHasEscrow { });
+    }
+
+    STAGE_SALES.save(deps.storage, key, &sale)?;
+    Ok(Response::new()
+    .add_attribute("action", "Set stage sale")
+    .add_attribute("stage", stage)
+    .add_attribute("price", sale.price.to_string()))
+}
+
+//sale tokens bought with `payment`, both in their smallest units and rounded down
+pub fn tokens_for_payment(sale: &StageSale, payment: Uint128) -> StdResult<Uint128>
+{
+    if sale.price.is_zero() {
+        return Err(StdError::generic_err("Invalid price"));
+    }
+    let ten = Uint256::from(10u8);
+    let numerator = Uint256::from(payment)
+        * ten.pow(sale.token_decimals as u32)
+        * Uint256::from(sale.price.denominator());
+    let denominator = Uint256::from(sale.price.numerator())
+        * ten.pow(sale.payment_decimals as u32);
+    Uint128::try_from(numerator / denominator)
+        .map_err(|_| StdError::generic_err("Token amount overflow"))
+}
+
+//payment buying `tokens` at the sale price, the inverse of tokens_for_payment
+fn payment_for_tokens(sale: &StageSale, tokens: Uint128) -> StdResult<Uint128>
+{
+    let ten = Uint256::from(10u8);
+    let numerator = Uint256::from(tokens)
+        * ten.pow(sale.payment_decimals as u32)
+        * Uint256::from(sale.price.numerator());
+    let denominator = Uint256::from(sale.price.denominator())
+        * ten.pow(sale.token_decimals as u32);
+    Uint128::try_from(numerator / denominator)
+        .map_err(|_| StdError::generic_err("Payment amount overflow"))
+}
+
+pub fn try_approvemilestone(deps: DepsMut, info: MessageInfo, project_id: Uint128, milestone_index: u32)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+
+    let mut treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
+    let milestone = treasury.milestones.get_mut(milestone_index as usize)
+        .ok_or(ContractError::MilestoneNotFound { })?;
+    milestone.approved = true;
+
+    TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+    Ok(Response::new()
+    .add_attribute("action", "Approve milestone")
+    .add_attribute("milestone_index", milestone_index.to_string()))
+}
+
+pub fn try_releasemilestone(deps: DepsMut, info: MessageInfo, project_id: Uint128, milestone_index: u32)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+
+    check_project_open(deps.storage, project_id)?;
+    let mut treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
+    //funds deposited in the money market have to be redeemed first
+    let position = MARKET_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+        .unwrap_or_default();
+    let locked = idle_funds(&treasury, &position)?;
+    let milestone = treasury.milestones.get_mut(milestone_index as usize)
+        .ok_or(ContractError::MilestoneNotFound { })?;
+    if !milestone.approved {
+        return Err(ContractError::MilestoneNotApproved { });
+    }
+    if milestone.released {
+        return Err(ContractError::MilestoneAlreadyReleased { });
+    }
+    if milestone.amount > locked {
+        return Err(ContractError::NotEnoughBalance { });
+    }
+    milestone.released = true;
+    let amount = milestone.amount;
+    treasury.released += amount;
+    TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+
+    let msg_release = BankMsg::Send {
+        to_address: treasury.wallet.to_string(),
+        amount: vec![Coin{ denom: treasury.denom, amount }],
+    };
+
+    Ok(Response::new()
+    .add_message(msg_release)
+    .add_attribute("action", "Release milestone")
+    .add_attribute("milestone_index", milestone_index.to_string())
+    .add_attribute("amount", amount.to_string()))
+}
+
+pub fn default_voting_config() -> VotingConfig
+{
+    VotingConfig {
+        quorum: Decimal::percent(30),
+        threshold: Decimal::percent(50),
+        voting_period: 7 * 24 * 60 * 60,
+    }
+}
+
+//voting weight of an investor is the total amount bought over all stages
+pub fn user_weight(x: &ProjectInfo, wallet: &Addr) -> Uint128
+{
+    x.seed_users.iter()
+        .chain(x.presale_users.iter())
+        .chain(x.ido_users.iter())
+        .filter(|user| user.wallet_address == *wallet)
+        .map(|user| user.total_amount)
+        .sum()
+}
+
+pub fn total_weight(x: &ProjectInfo) -> Uint128
+{
+    x.seed_users.iter()
+        .chain(x.presale_users.iter())
+        .chain(x.ido_users.iter())
+        .map(|user| user.total_amount)
+        .sum()
+}
+
+pub fn try_setvotingconfig(deps: DepsMut, info: MessageInfo, quorum: Decimal, threshold: Decimal,
+    voting_period: u64)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    if quorum > Decimal::one() || threshold > Decimal::one()
+        || voting_period == 0 || voting_period > MAX_VOTING_PERIOD {
+        return Err(ContractError::InvalidVotingConfig { });
+    }
+
+    VOTING_CONFIG.save(deps.storage, &VotingConfig{ quorum, threshold, voting_period })?;
+    Ok(Response::new()
+    .add_attribute("action", "Set voting config"))
+}
+
+pub fn try_proposewinddown(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128,
+    description: String)
+    ->Result<Response, ContractError>
+{
+    let x = load_project(deps.storage, project_id)?;
+    if user_weight(&x, &info.sender).is_zero() {
+        return Err(ContractError::NotInvestor { });
+    }
+    check_project_open(deps.storage, project_id)?;
+
+    if has_open_proposal(deps.storage, project_id)? {
+        return Err(ContractError::ProposalAlreadyOpen { });
+    }
+    let count = PROPOSAL_COUNT.may_load(deps.storage, project_id.u128().into())?.unwrap_or(0);
+
+    let config = VOTING_CONFIG.may_load(deps.storage)?.unwrap_or_else(default_voting_config);
+    let proposal_id = count + 1;
+    let end_time = env.block.time.seconds().checked_add(config.voting_period)
+        .ok_or(ContractError::InvalidVotingConfig { })?;
+    PROPOSALS.save(deps.storage, (project_id.u128().into(), proposal_id.into()), &Proposal{
+        id: proposal_id,
+        description,
+        proposer: info.sender,
+        end_time,
+        yes_weight: Uint128::zero(),
+        no_weight: Uint128::zero(),
+        total_weight: total_weight(&x),
+        status: ProposalStatus::Open,
+    })?;
+    PROPOSAL_COUNT.save(deps.storage, project_id.u128().into(), &proposal_id)?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Propose wind down")
+    .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+pub fn try_vote(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, proposal_id: u64,
+    wind_down: bool)
+    ->Result<Response, ContractError>
+{
+    let x = load_project(deps.storage, project_id)?;
+    let weight = user_weight(&x, &info.sender);
+    if weight.is_zero() {
+        return Err(ContractError::NotInvestor { });
+    }
+
+    let key = (project_id.u128().into(), proposal_id.into());
+    let mut proposal = PROPOSALS.may_load(deps.storage, key)?
+        .ok_or(ContractError::ProposalNotFound { })?;
+    if proposal.status != ProposalStatus::Open {
+        return Err(ContractError::ProposalNotOpen { });
+    }
+    if env.block.time.seconds() >= proposal.end_time {
+        return Err(ContractError::VotingEnded { });
+    }
+
+    let vote_key = (project_id.u128().into(), proposal_id.into(), &info.sender);
+    if VOTES.has(deps.storage, vote_key.clone()) {
+        return Err(ContractError::AlreadyVoted { });
+    }
+    VOTES.save(deps.storage, vote_key, &wind_down)?;
+
+    if wind_down {
+        proposal.yes_weight += weight;
+    } else {
+        proposal.no_weight += weight;
+    }
+    PROPOSALS.save(deps.storage, (project_id.u128().into(), proposal_id.into()), &proposal)?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Vote")
+    .add_attribute("wind_down", wind_down.to_string())
+    .add_attribute("weight", weight.to_string()))
+}
+
+pub fn try_endproposal(mut deps: DepsMut, env: Env, project_id: Uint128, proposal_id: u64)
+    ->Result<Response, ContractError>
+{
+    let key = (project_id.u128().into(), proposal_id.into());
+    let mut proposal = PROPOSALS.may_load(deps.storage, key)?
+        .ok_or(ContractError::ProposalNotFound { })?;
+    if proposal.status != ProposalStatus::Open {
+        return Err(ContractError::ProposalNotOpen { });
+    }
+    if env.block.time.seconds() < proposal.end_time {
+        return Err(ContractError::VotingNotEnded { });
+    }
+
+    let config = VOTING_CONFIG.may_load(deps.storage)?.unwrap_or_else(default_voting_config);
+    let cast = proposal.yes_weight + proposal.no_weight;
+    let passed = !cast.is_zero()
+        && Decimal::from_ratio(cast, proposal.total_weight) >= config.quorum
+        && Decimal::from_ratio(proposal.yes_weight, cast) > config.threshold;
+
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    if passed {
+        proposal.status = ProposalStatus::Passed;
+
+        //funds deposited in the money market are redeemed into the refund pool
+        let position = MARKET_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+            .unwrap_or_default();
+        if !position.shares.is_zero() {
+            let (redeem_msgs, _, _) = redeem_position(deps.branch(), project_id, None)?;
+            msgs = redeem_msgs;
+        }
+
+        //escrowed funds and unreleased tokens are frozen for pro-rata refunds
+        let x = load_project(deps.storage, project_id)?;
+        let fund_pool = match TREASURIES.may_load(deps.storage, project_id.u128().into())? {
+            Some(treasury) => treasury.total_raised.checked_sub(treasury.released)
+                .map_err(StdError::from)?,
+            None => Uint128::zero(),
+        };
+        //refunds are weighted by the allocations at the wind down
+        for user in x.seed_users.iter().chain(x.presale_users.iter()).chain(x.ido_users.iter()) {
+            WIND_DOWN_WEIGHTS.update(deps.storage, (project_id.u128().into(), &user.wallet_address),
+                |v| -> StdResult<_> { Ok(v.unwrap_or_default() + user.total_amount) })?;
+        }
+        let token_pool: Uint128 = x.seed_users.iter()
+            .chain(x.presale_users.iter())
+            .chain(x.ido_users.iter())
+            .map(|user| user.total_amount - user.released_amount)
+            .sum();
+        WIND_DOWNS.save(deps.storage, project_id.u128().into(), &WindDown{
+            proposal_id,
+            fund_pool,
+            token_pool,
+            total_weight: total_weight(&x),
+        })?;
+    } else {
+        proposal.status = ProposalStatus::Rejected;
+    }
+    PROPOSALS.save(deps.storage, (project_id.u128().into(), proposal_id.into()), &proposal)?;
+
+    Ok(Response::new()
+    .add_messages(msgs)
+    .add_attribute("action", "End proposal")
+    .add_attribute("passed", passed.to_string()))
+}
+
+pub fn try_claimrefund(deps: DepsMut, info: MessageInfo, project_id: Uint128)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    if CANCELLED_PROJECTS.has(deps.storage, project_id.u128().into()) {
+        return claim_cancel_refund(deps, info, project_id);
+    }
+    let wind_down = WIND_DOWNS.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::ProjectNotWoundDown { })?;
+    let x = load_project(deps.storage, project_id)?;
+    let weight = WIND_DOWN_WEIGHTS.may_load(deps.storage, (project_id.u128().into(), &info.sender))?
+        .unwrap_or_default();
+    if weight.is_zero() {
+        return Err(ContractError::NotInvestor { });
+    }
+    if REFUND_CLAIMED.has(deps.storage, (project_id.u128().into(), &info.sender)) {
+        return Err(ContractError::RefundAlreadyClaimed { });
+    }
+    REFUND_CLAIMED.save(deps.storage, (project_id.u128().into(), &info.sender), &true)?;
+
+    let fund_amount = wind_down.fund_pool.multiply_ratio(weight, wind_down.total_weight);
+    let token_amount = wind_down.token_pool.multiply_ratio(weight, wind_down.total_weight);
+
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    let mut emitted: Vec<Event> = Vec::new();
+    if !fund_amount.is_zero() {
+        let mut treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+        treasury.released += fund_amount;
+        TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+        emitted.push(events::claim(project_id, &info.sender, "refund", &treasury.denom, fund_amount));
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin{ denom: treasury.denom, amount: fund_amount }],
+        }));
+    }
+    if !token_amount.is_zero() {
+        spend_project_tokens(deps.storage, project_id, token_amount)?;
+        emitted.push(events::claim(project_id, &info.sender, "refund", &x.config.token_addr, token_amount));
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: x.config.token_addr,
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: token_amount,
+            })?,
+            funds: Vec::new()
+        }));
+    }
+
+    Ok(Response::new()
+    .add_messages(msgs)
+    .add_events(emitted)
+    .add_attribute("action", "Claim refund")
+    .add_attribute("fund_amount", fund_amount.to_string())
+    .add_attribute("token_amount", token_amount.to_string()))
+}
+
+//wound down and cancelled projects take no more contributions, bids or releases
+//treasury funds held by the contract, outside the money market
+fn idle_funds(treasury: &Treasury, position: &MarketPosition) -> StdResult<Uint128>
+{
+    Ok(treasury.total_raised.checked_sub(treasury.released)?.checked_sub(position.principal)?)
+}
+
+fn check_project_open(storage: &dyn Storage, project_id: Uint128) -> Result<(), ContractError>
+{
+    if WIND_DOWNS.has(storage, project_id.u128().into()) {
+        return Err(ContractError::ProjectWoundDown { });
+    }
+    if CANCELLED_PROJECTS.has(storage, project_id.u128().into()) {
+        return Err(ContractError::ProjectCancelled { });
+    }
+    Ok(())
+}
+
+//sale tokens leaving the contract for the project, out of its deposits
+fn spend_project_tokens(storage: &mut dyn Storage, project_id: Uint128, amount: Uint128)
+    -> Result<(), ContractError>
+{
+    let tokens = PROJECT_TOKENS.may_load(storage, project_id.u128().into())?.unwrap_or_default();
+    if tokens < amount {
+        return Err(ContractError::NotEnoughBalance { });
+    }
+    PROJECT_TOKENS.save(storage, project_id.u128().into(), &(tokens - amount))?;
+    Ok(())
+}
+
+//allocations are frozen once StartVesting has run, later changes would shift
+//the sync pages and would never reach the vesting contract; they are also the
+//voting weights, so they can't change while a proposal is open
+fn check_allocations_open(storage: &dyn Storage, project_id: Uint128) -> Result<(), ContractError>
+{
+    if VESTING_USER_SYNC.has(storage, project_id.u128().into()) {
+        return Err(ContractError::VestingStarted { });
+    }
+    if has_open_proposal(storage, project_id)? {
+        return Err(ContractError::ProposalOpen { });
+    }
+    Ok(())
+}
+
+fn has_open_proposal(storage: &dyn Storage, project_id: Uint128) -> StdResult<bool>
+{
+    let count = PROPOSAL_COUNT.may_load(storage, project_id.u128().into())?.unwrap_or(0);
+    if count == 0 {
+        return Ok(false);
+    }
+    let last = PROPOSALS.load(storage, (project_id.u128().into(), count.into()))?;
+    Ok(last.status == ProposalStatus::Open)
+}
+
+pub fn try_cancelproject(deps: DepsMut, info: MessageInfo, project_id: Uint128)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    check_project_open(deps.storage, project_id)?;
+
+    //contributions can only be refunded in full while nothing is paid out
+    if let Some(treasury) = TREASURIES.may_load(deps.storage, project_id.u128().into())? {
+        if !treasury.released.is_zero() {
+            return Err(ContractError::FundsReleased { });
+        }
+    }
+    if MARKET_POSITIONS.has(deps.storage, project_id.u128().into()) {
+        let position = MARKET_POSITIONS.load(deps.storage, project_id.u128().into())?;
+        if !position.principal.is_zero() {
+            return Err(ContractError::FundsReleased { });
+        }
+        //the treasury no longer holds every contribution to refund
+        if !position.losses.is_zero() {
+            return Err(ContractError::FundsLost { });
+        }
+    }
+    if x.seed_users.iter().chain(x.presale_users.iter()).chain(x.ido_users.iter())
+        .any(|user| !user.released_amount.is_zero()) {
+        return Err(ContractError::AllocationsReleased { });
+    }
+    CANCELLED_PROJECTS.save(deps.storage, project_id.u128().into(), &true)?;
+
+    //deposited sale tokens go back to the project admin
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    let token_amount = PROJECT_TOKENS.may_load(deps.storage, project_id.u128().into())?
+        .unwrap_or_default();
+    PROJECT_TOKENS.remove(deps.storage, project_id.u128().into());
+    if !token_amount.is_zero() {
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: x.config.token_addr,
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: x.config.owner.to_string(),
+                amount: token_amount,
+            })?,
+            funds: Vec::new()
+        }));
+    }
+
+    Ok(Response::new()
+    .add_messages(msgs)
+    .add_attribute("action", "Cancel project")
+    .add_attribute("project_id", project_id.to_string())
+    .add_attribute("token_amount", token_amount.to_string()))
+}
+
+fn claim_cancel_refund(deps: DepsMut, info: MessageInfo, project_id: Uint128)
+    ->Result<Response, ContractError>
+{
+    let amount = CONTRIBUTIONS.may_load(deps.storage, (project_id.u128().into(), &info.sender))?
+        .unwrap_or_default();
+    if amount.is_zero() {
+        return Err(ContractError::NotInvestor { });
+    }
+    //yield earned until the cancel stays claimable
+    settle_yield(deps.storage, project_id, &info.sender)?;
+    CONTRIBUTIONS.remove(deps.storage, (project_id.u128().into(), &info.sender));
+
+    let mut treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+    treasury.released += amount;
+    TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+
+    Ok(Response::new()
+    .add_event(events::claim(project_id, &info.sender, "refund", &treasury.denom, amount))
+    .add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin{ denom: treasury.denom, amount }],
+    })
+    .add_attribute("action", "Claim refund")
+    .add_attribute("fund_amount", amount.to_string())
+    .add_attribute("token_amount", Uint128::zero().to_string()))
+}
+
+pub fn try_archiveproject(deps: DepsMut, info: MessageInfo, project_id: Uint128, archived: bool)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    if archived {
+        let finished = CANCELLED_PROJECTS.has(deps.storage, project_id.u128().into())
+            || WIND_DOWNS.has(deps.storage, project_id.u128().into())
+            || SALE_FINALIZED.has(deps.storage, project_id.u128().into());
+        if !finished {
+            return Err(ContractError::ProjectNotFinished { });
+        }
+        ARCHIVED_PROJECTS.save(deps.storage, project_id.u128().into(), &true)?;
+    } else {
+        ARCHIVED_PROJECTS.remove(deps.storage, project_id.u128().into());
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Archive project")
+    .add_attribute("archived", archived.to_string()))
+}
+
+pub fn try_receive(deps: DepsMut, info: MessageInfo, msg: Cw20ReceiveMsg)
+    ->Result<Response, ContractError>
+{
+    match from_binary(&msg.msg)? {
+        Cw20HookMsg::DepositTokens{ project_id } => {
+            let x = load_project(deps.storage, project_id)?;
+            if x.config.token_addr.is_empty() || info.sender != x.config.token_addr {
+                return Err(ContractError::WrongToken { });
+            }
+            check_project_open(deps.storage, project_id)?;
+            PROJECT_TOKENS.update(deps.storage, project_id.u128().into(), |tokens| -> StdResult<_> {
+                Ok(tokens.unwrap_or_default() + msg.amount)
+            })?;
+
+            Ok(Response::new()
+            .add_attribute("action", "Deposit tokens")
+            .add_attribute("project_id", project_id.to_string())
+            .add_attribute("from", msg.sender)
+            .add_attribute("amount", msg.amount.to_string()))
+        }
+    }
+}
+
+pub fn try_setmoneymarket(deps: DepsMut, info: MessageInfo, market_addr: String,
+    platform_wallet: String, platform_share: Decimal, project_share: Decimal, investor_share: Decimal)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    if platform_share + project_share + investor_share != Decimal::one() {
+        return Err(ContractError::InvalidYieldSplit { });
+    }
+
+    MONEY_MARKET.save(deps.storage, &MoneyMarketConfig{
+        market_addr: check_addr(deps.api, &market_addr)?,
+        platform_wallet: check_addr(deps.api, &platform_wallet)?,
+        platform_share,
+        project_share,
+        investor_share,
+    })?;
+    Ok(Response::new()
+    .add_attribute("action", "Set money market"))
+}
+
+pub fn try_depositidlefunds(deps: DepsMut, info: MessageInfo, project_id: Uint128, amount: Uint128)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    check_project_open(deps.storage, project_id)?;
+    let market = MONEY_MARKET.may_load(deps.storage)?
+        .ok_or(ContractError::NoMoneyMarket { })?;
+    let treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
+
+    let mut position = MARKET_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+        .unwrap_or_default();
+    let idle = idle_funds(&treasury, &position)?;
+    if amount.is_zero() || amount > idle {
+        return Err(ContractError::NotEnoughBalance { });
+    }
+
+    let rate: ExchangeRateResponse = deps.querier.query_wasm_smart(
+        market.market_addr.to_string(),
+        &marketQueryMsg::ExchangeRate { }
+    )?;
+    position.principal += amount;
+    if rate.exchange_rate.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err("Invalid exchange rate")));
+    }
+    position.shares += amount.multiply_ratio(rate.exchange_rate.denominator(),
+        rate.exchange_rate.numerator());
+    MARKET_POSITIONS.save(deps.storage, project_id.u128().into(), &position)?;
+
+    let msg_deposit = WasmMsg::Execute {
+        contract_addr: market.market_addr.to_string(),
+        msg: to_binary(&marketExecuteMsg::Deposit { })?,
+        funds: vec![Coin{ denom: treasury.denom, amount }]
+    };
+
+    Ok(Response::new()
+    .add_message(msg_deposit)
+    .add_attribute("action", "Deposit idle funds")
+    .add_attribute("amount", amount.to_string()))
+}
+
+pub fn try_redeemidlefunds(deps: DepsMut, info: MessageInfo, project_id: Uint128, shares: Option<Uint128>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let (msgs, value, earned) = redeem_position(deps, project_id, shares)?;
 
     Ok(Response::new()
-        .add_attribute("method", "instantiate"))
+    .add_messages(msgs)
+    .add_attribute("action", "Redeem idle funds")
+    .add_attribute("value", value.to_string())
+    .add_attribute("yield", earned.to_string()))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    msg: ExecuteMsg,
-) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::SetConfig{ admin, vesting_addr }
-            => try_setconfig(deps, _env, info, admin, vesting_addr ),
+//redeems market shares of a project, returning the messages, the redeemed value and the yield
+fn redeem_position(deps: DepsMut, project_id: Uint128, shares: Option<Uint128>)
+    ->Result<(Vec<CosmosMsg>, Uint128, Uint128), ContractError>
+{
+    let market = MONEY_MARKET.may_load(deps.storage)?
+        .ok_or(ContractError::NoMoneyMarket { })?;
+    let mut treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
+    let mut position = MARKET_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+        .unwrap_or_default();
 
-        ExecuteMsg::AddProject{ project_id, admin, token_addr, vesting_params, start_time }
-            => try_addproject(deps, info, project_id, admin, token_addr, vesting_params, start_time ),
+    let shares = shares.unwrap_or(position.shares);
+    if shares.is_zero() || shares > position.shares {
+        return Err(ContractError::NotEnoughBalance { });
+    }
 
-        ExecuteMsg::SetProjectConfig{ project_id, admin, token_addr, start_time} 
-            => try_setprojectconfig(deps, info, project_id, admin, token_addr, start_time),
+    let rate: ExchangeRateResponse = deps.querier.query_wasm_smart(
+        market.market_addr.to_string(),
+        &marketQueryMsg::ExchangeRate { }
+    )?;
+    let value = shares * rate.exchange_rate;
+    let principal = position.principal.multiply_ratio(shares, position.shares);
+    position.principal -= principal;
+    position.shares -= shares;
 
-        ExecuteMsg::AddUser{ project_id, wallet, stage, amount} 
-            => try_adduser(deps, info, project_id, wallet, stage, amount),
+    //a loss on the market is carried by the treasury
+    let earned = value.saturating_sub(principal);
+    let loss = principal.saturating_sub(value);
+    treasury.total_raised -= loss;
+    position.losses += loss;
+    TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
 
-        ExecuteMsg::SetVestingParameters{ project_id, params }
-            => try_setvestingparameters(deps, info, project_id, params),
+    let platform_yield = earned * market.platform_share;
+    let project_yield = earned * market.project_share;
+    let investor_yield = earned - platform_yield - project_yield;
+    position.investor_yield += investor_yield;
+    //contributions count at their full amount, losses included
+    let contributed = treasury.total_raised + position.losses;
+    if !contributed.is_zero() {
+        position.yield_index = position.yield_index + Decimal::from_ratio(investor_yield, contributed);
+    }
+    MARKET_POSITIONS.save(deps.storage, project_id.u128().into(), &position)?;
 
-        ExecuteMsg::SetSeedUsers { project_id, user_infos } 
-            =>  try_setseedusers(deps, info, project_id, user_infos),
+    let mut msgs: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: market.market_addr.to_string(),
+        msg: to_binary(&marketExecuteMsg::Redeem { shares })?,
+        funds: Vec::new()
+    })];
+    if !platform_yield.is_zero() {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: market.platform_wallet.to_string(),
+            amount: vec![Coin{ denom: treasury.denom.clone(), amount: platform_yield }],
+        }));
+    }
+    if !project_yield.is_zero() {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: treasury.wallet.to_string(),
+            amount: vec![Coin{ denom: treasury.denom, amount: project_yield }],
+        }));
+    }
+    Ok((msgs, value, earned))
+}
 
-        ExecuteMsg::AddSeedUser { project_id, wallet, amount } 
-            =>  try_addseeduser(deps, info, project_id, wallet, amount),
+//credits the yield earned by a contribution since its last checkpoint
+fn settle_yield(storage: &mut dyn Storage, project_id: Uint128, wallet: &Addr) -> StdResult<YieldCheckpoint>
+{
+    let index = MARKET_POSITIONS.may_load(storage, project_id.u128().into())?
+        .unwrap_or_default().yield_index;
+    let contribution = CONTRIBUTIONS.may_load(storage, (project_id.u128().into(), wallet))?
+        .unwrap_or_default();
+    let mut checkpoint = YIELD_CHECKPOINTS.may_load(storage, (project_id.u128().into(), wallet))?
+        .unwrap_or_default();
+    checkpoint.pending += (index - checkpoint.index) * contribution;
+    checkpoint.index = index;
+    YIELD_CHECKPOINTS.save(storage, (project_id.u128().into(), wallet), &checkpoint)?;
+    Ok(checkpoint)
+}
 
-        ExecuteMsg::SetPresaleUsers { project_id, user_infos } 
-            =>  try_setpresaleusers(deps, info, project_id, user_infos),
+//later contributions only share the yield earned after them
+fn add_contribution(storage: &mut dyn Storage, project_id: Uint128, wallet: &Addr, amount: Uint128)
+    -> StdResult<()>
+{
+    settle_yield(storage, project_id, wallet)?;
+    CONTRIBUTIONS.update(storage, (project_id.u128().into(), wallet),
+        |v| -> StdResult<_> { Ok(v.unwrap_or_default() + amount) })?;
+    Ok(())
+}
 
-        ExecuteMsg::AddPresaleUser { project_id, wallet, amount } 
-            =>  try_addpresaleuser(deps, info, project_id, wallet, amount),
+pub fn try_claimyield(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128,
+    kyc: Option<KycProof>)
+    ->Result<Response, ContractError>
+{
+    check_not_denied(deps.storage, project_id, &info.sender)?;
+    check_kyc(deps.as_ref(), &env, project_id, &info.sender, kyc)?;
+    let treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
 
-        ExecuteMsg::SetIDOUsers { project_id, user_infos } 
-            =>  try_setidousers(deps, info, project_id, user_infos),
+    let mut checkpoint = settle_yield(deps.storage, project_id, &info.sender)?;
+    let amount = checkpoint.pending;
+    if amount.is_zero() {
+        return Err(ContractError::NoYield { });
+    }
+    checkpoint.pending = Uint128::zero();
+    YIELD_CHECKPOINTS.save(deps.storage, (project_id.u128().into(), &info.sender), &checkpoint)?;
 
-        ExecuteMsg::AddIDOUser { project_id, wallet, amount } 
-            =>  try_addidouser(deps, info, project_id, wallet, amount),
-        
-        ExecuteMsg::StartVesting { project_id }
-            =>  try_startvesting(deps, _env, info, project_id),
+    Ok(Response::new()
+    .add_event(events::claim(project_id, &info.sender, "yield", &treasury.denom, amount))
+    .add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin{ denom: treasury.denom, amount }],
+    })
+    .add_attribute("action", "Claim yield")
+    .add_attribute("amount", amount.to_string()))
+}
+
+pub fn try_setfeeconfig(deps: DepsMut, info: MessageInfo, collector: String, raise_fee: Decimal,
+    token_fee: Decimal)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    if raise_fee > Decimal::one() || token_fee > Decimal::one() {
+        return Err(ContractError::InvalidFee { });
+    }
+
+    FEE_CONFIG.save(deps.storage, &FeeConfig{
+        collector: check_addr(deps.api, &collector)?,
+        rates: FeeRates{ raise_fee, token_fee },
+    })?;
+    Ok(Response::new()
+    .add_attribute("action", "Set fee config"))
+}
+
+pub fn try_setprojectfees(deps: DepsMut, info: MessageInfo, project_id: Uint128, fees: Option<FeeRates>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    load_project(deps.storage, project_id)?;
+
+    match fees {
+        Some(fees) => {
+            if fees.raise_fee > Decimal::one() || fees.token_fee > Decimal::one() {
+                return Err(ContractError::InvalidFee { });
+            }
+            PROJECT_FEES.save(deps.storage, project_id.u128().into(), &fees)?;
+        }
+        None => PROJECT_FEES.remove(deps.storage, project_id.u128().into()),
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set project fees"))
+}
+
+//fees owed on the current raise and sold tokens, minus what was already paid
+pub fn project_fees(storage: &dyn Storage, project_id: Uint128) -> StdResult<FeesResponse>
+{
+    let x = PROJECT_INFOS.load(storage, project_id.u128().into())?;
+    let rates = match PROJECT_FEES.may_load(storage, project_id.u128().into())? {
+        Some(rates) => rates,
+        None => match FEE_CONFIG.may_load(storage)? {
+            Some(config) => config.rates,
+            None => FeeRates{ raise_fee: Decimal::zero(), token_fee: Decimal::zero() },
+        },
+    };
+    let raised = match TREASURIES.may_load(storage, project_id.u128().into())? {
+        Some(treasury) => treasury.total_raised,
+        None => Uint128::zero(),
+    };
+    let paid = FEES_PAID.may_load(storage, project_id.u128().into())?.unwrap_or_default();
+
+    Ok(FeesResponse{
+        accrued_funds: (raised * rates.raise_fee).saturating_sub(paid.funds),
+        accrued_tokens: (total_weight(&x) * rates.token_fee).saturating_sub(paid.tokens),
+        rates,
+        paid,
+    })
+}
+
+pub fn try_finalizesale(deps: DepsMut, info: MessageInfo, project_id: Uint128)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    check_project_open(deps.storage, project_id)?;
+    if SALE_FINALIZED.has(deps.storage, project_id.u128().into()) {
+        return Err(ContractError::SaleFinalized { });
+    }
+    SALE_FINALIZED.save(deps.storage, project_id.u128().into(), &true)?;
+
+    let fees = project_fees(deps.storage, project_id)?;
+    let mut msgs: Vec<CosmosMsg> = Vec::new();
+    if !fees.accrued_funds.is_zero() || !fees.accrued_tokens.is_zero() {
+        let collector = FEE_CONFIG.load(deps.storage)?.collector;
+
+        if !fees.accrued_funds.is_zero() {
+            let mut treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+            let position = MARKET_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+                .unwrap_or_default();
+            if idle_funds(&treasury, &position)? < fees.accrued_funds {
+                return Err(ContractError::NotEnoughBalance { });
+            }
+            treasury.released += fees.accrued_funds;
+            TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+            msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: collector.to_string(),
+                amount: vec![Coin{ denom: treasury.denom, amount: fees.accrued_funds }],
+            }));
+        }
+        if !fees.accrued_tokens.is_zero() {
+            spend_project_tokens(deps.storage, project_id, fees.accrued_tokens)?;
+            msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: x.config.token_addr,
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: collector.to_string(),
+                    amount: fees.accrued_tokens,
+                })?,
+                funds: Vec::new()
+            }));
+        }
+
+        let mut paid = fees.paid;
+        paid.funds += fees.accrued_funds;
+        paid.tokens += fees.accrued_tokens;
+        FEES_PAID.save(deps.storage, project_id.u128().into(), &paid)?;
+    }
+
+    Ok(Response::new()
+    .add_messages(msgs)
+    .add_attribute("action", "Finalize sale")
+    .add_attribute("fee_funds", fees.accrued_funds.to_string())
+    .add_attribute("fee_tokens", fees.accrued_tokens.to_string()))
+}
+
+pub fn try_setliquidityconfig(deps: DepsMut, info: MessageInfo, project_id: Uint128,
+    liquidity: Option<LiquidityConfig>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    if LIQUIDITY_POSITIONS.has(deps.storage, project_id.u128().into()) {
+        return Err(ContractError::LiquidityProvided { });
+    }
+
+    match liquidity {
+        Some(liquidity) => {
+            check_addr(deps.api, &liquidity.factory)?;
+            if liquidity.funds_share.is_zero() || liquidity.funds_share > Decimal::one() {
+                return Err(ContractError::InvalidLiquidityShare { });
+            }
+            if liquidity.price.is_zero() {
+                return Err(ContractError::InvalidPrice { });
+            }
+            LIQUIDITY_CONFIGS.save(deps.storage, project_id.u128().into(), &liquidity)?;
+        }
+        None => LIQUIDITY_CONFIGS.remove(deps.storage, project_id.u128().into()),
+    }
+    Ok(Response::new()
+    .add_attribute("action", "Set liquidity config"))
+}
+
+fn liquidity_asset_infos(token_addr: &str, denom: &str) -> [AssetInfo; 2]
+{
+    [
+        AssetInfo::Token{ contract_addr: token_addr.to_string() },
+        AssetInfo::NativeToken{ denom: denom.to_string() },
+    ]
+}
+
+//None when the factory answers the pair query with an error, the factory only fails it
+//for a missing pair; errors reaching the factory are returned
+fn query_pair(deps: Deps, factory: &str, asset_infos: [AssetInfo; 2]) -> StdResult<Option<PairInfo>>
+{
+    let request: QueryRequest<Empty> = QueryRequest::Wasm(WasmQuery::Smart{
+        contract_addr: factory.to_string(),
+        msg: to_binary(&FactoryQueryMsg::Pair{ asset_infos })?,
+    });
+    match deps.querier.raw_query(&to_vec(&request)?) {
+        SystemResult::Ok(ContractResult::Ok(pair)) => Ok(Some(from_binary(&pair)?)),
+        SystemResult::Ok(ContractResult::Err(_)) => Ok(None),
+        SystemResult::Err(e) => Err(StdError::generic_err(format!("Querier system error: {}", e))),
+    }
+}
+
+fn lp_balance(deps: Deps, env: &Env, lp_token: &Addr) -> StdResult<Uint128>
+{
+    let balance: Cw20BalanceResponse = deps.querier.query_wasm_smart(lp_token,
+        &Cw20QueryMsg::Balance{ address: env.contract.address.to_string() })?;
+    Ok(balance.balance)
+}
+
+//allowance for the pair to pull the project tokens, then the deposit of both assets;
+//the LP tokens received are counted for the project in the reply of the deposit
+fn provide_liquidity_msgs(deps: DepsMut, env: &Env, project_id: Uint128, x: &ProjectInfo, denom: &str,
+    pair: &PairInfo, position: &LiquidityPosition) ->StdResult<Vec<SubMsg>>
+{
+    let balance = lp_balance(deps.as_ref(), env, &pair.liquidity_token)?;
+    PENDING_LIQUIDITY_PROJECT.save(deps.storage, &project_id)?;
+    PENDING_LP_BALANCE.save(deps.storage, &balance)?;
+
+    let pair = &pair.contract_addr;
+    let asset_infos = liquidity_asset_infos(&x.config.token_addr, denom);
+    Ok(vec![
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: x.config.token_addr.clone(),
+            msg: to_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                spender: pair.to_string(),
+                amount: position.tokens,
+                expires: None,
+            })?,
+            funds: Vec::new()
+        }),
+        SubMsg::reply_on_success(WasmMsg::Execute {
+            contract_addr: pair.to_string(),
+            msg: to_binary(&PairExecuteMsg::ProvideLiquidity {
+                assets: [
+                    Asset{ info: asset_infos[0].clone(), amount: position.tokens },
+                    Asset{ info: asset_infos[1].clone(), amount: position.funds },
+                ],
+                slippage_tolerance: None,
+                receiver: None,
+            })?,
+            funds: vec![Coin{ denom: denom.to_string(), amount: position.funds }]
+        }, LIQUIDITY_PROVIDE_REPLY_ID),
+    ])
+}
+
+pub fn try_bootstrapliquidity(mut deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    if x.config.token_addr.is_empty() {
+        return Err(ContractError::NotTokenAddr { });
+    }
+    check_project_open(deps.storage, project_id)?;
+    if !SALE_FINALIZED.has(deps.storage, project_id.u128().into()) {
+        return Err(ContractError::SaleNotFinalized { });
+    }
+    if LIQUIDITY_POSITIONS.has(deps.storage, project_id.u128().into()) {
+        return Err(ContractError::LiquidityProvided { });
+    }
+    let config = LIQUIDITY_CONFIGS.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoLiquidityConfig { })?;
+
+    let mut treasury = TREASURIES.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoTreasury { })?;
+    let position = MARKET_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+        .unwrap_or_default();
+    let funds = treasury.total_raised * config.funds_share;
+    if funds.is_zero() || idle_funds(&treasury, &position)? < funds {
+        return Err(ContractError::NotEnoughBalance { });
+    }
+    let tokens = tokens_for_payment(&StageSale{
+        price: config.price,
+        payment_decimals: config.payment_decimals,
+        token_decimals: config.token_decimals,
+        sale_type: SaleType::FixedPrice,
+    }, funds)?;
+    treasury.released += funds;
+    TREASURIES.save(deps.storage, project_id.u128().into(), &treasury)?;
+    spend_project_tokens(deps.storage, project_id, tokens)?;
+
+    let asset_infos = liquidity_asset_infos(&x.config.token_addr, &treasury.denom);
+    let pair = query_pair(deps.as_ref(), &config.factory, asset_infos.clone())?;
+    let position = LiquidityPosition{
+        pair: pair.as_ref().map(|p| p.contract_addr.clone()),
+        lp_token: pair.as_ref().map(|p| p.liquidity_token.clone()),
+        funds,
+        tokens,
+        provided_at: env.block.time.seconds(),
+        lp_claimed: Uint128::zero(),
+        lp_amount: Uint128::zero(),
+    };
+
+    let res = match pair {
+        Some(pair) => Response::new()
+            .add_submessages(provide_liquidity_msgs(deps.branch(), &env, project_id, &x, &treasury.denom,
+                &pair, &position)?),
+        None => {
+            //liquidity is provided in the reply, once the factory has created the pair
+            PENDING_LIQUIDITY_PROJECT.save(deps.storage, &project_id)?;
+            Response::new()
+                .add_submessage(SubMsg::reply_on_success(WasmMsg::Execute {
+                    contract_addr: config.factory,
+                    msg: to_binary(&FactoryExecuteMsg::CreatePair{ asset_infos })?,
+                    funds: Vec::new()
+                }, LIQUIDITY_CREATE_PAIR_REPLY_ID))
+        }
+    };
+    LIQUIDITY_POSITIONS.save(deps.storage, project_id.u128().into(), &position)?;
+
+    Ok(res
+    .add_attribute("action", "Bootstrap liquidity")
+    .add_attribute("funds", funds.to_string())
+    .add_attribute("tokens", tokens.to_string()))
+}
+
+fn handle_create_pair_reply(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let project_id = PENDING_LIQUIDITY_PROJECT.load(deps.storage)?;
+    PENDING_LIQUIDITY_PROJECT.remove(deps.storage);
+
+    let x = load_project(deps.storage, project_id)?;
+    let config = LIQUIDITY_CONFIGS.load(deps.storage, project_id.u128().into())?;
+    let treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+    let pair = query_pair(deps.as_ref(), &config.factory,
+        liquidity_asset_infos(&x.config.token_addr, &treasury.denom))?
+        .ok_or_else(|| StdError::generic_err("Pair is not created"))?;
+
+    let mut position = LIQUIDITY_POSITIONS.load(deps.storage, project_id.u128().into())?;
+    position.pair = Some(pair.contract_addr.clone());
+    position.lp_token = Some(pair.liquidity_token.clone());
+    LIQUIDITY_POSITIONS.save(deps.storage, project_id.u128().into(), &position)?;
+
+    Ok(Response::new()
+    .add_submessages(provide_liquidity_msgs(deps.branch(), &env, project_id, &x, &treasury.denom,
+        &pair, &position)?)
+    .add_attribute("action", "Create liquidity pair")
+    .add_attribute("pair", pair.contract_addr.to_string()))
+}
+
+fn handle_provide_liquidity_reply(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let project_id = PENDING_LIQUIDITY_PROJECT.load(deps.storage)?;
+    let before = PENDING_LP_BALANCE.load(deps.storage)?;
+    PENDING_LIQUIDITY_PROJECT.remove(deps.storage);
+    PENDING_LP_BALANCE.remove(deps.storage);
+
+    let mut position = LIQUIDITY_POSITIONS.load(deps.storage, project_id.u128().into())?;
+    let lp_token = position.lp_token.clone().ok_or(ContractError::NoLpTokens { })?;
+    position.lp_amount = lp_balance(deps.as_ref(), &env, &lp_token)?
+        .checked_sub(before)
+        .map_err(StdError::from)?;
+    LIQUIDITY_POSITIONS.save(deps.storage, project_id.u128().into(), &position)?;
+
+    Ok(Response::new()
+    .add_attribute("action", "Provide liquidity")
+    .add_attribute("lp_amount", position.lp_amount.to_string()))
+}
+
+pub fn try_claimlptokens(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    let config = LIQUIDITY_CONFIGS.load(deps.storage, project_id.u128().into())?;
+    let mut position = LIQUIDITY_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+        .ok_or(ContractError::NoLpTokens { })?;
+    let lp_token = position.lp_token.clone().ok_or(ContractError::NoLpTokens { })?;
+    let treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
 
+    let total = position.lp_amount;
+    let elapsed = env.block.time.seconds().saturating_sub(position.provided_at);
+    let vested = if elapsed < config.cliff {
+        Uint128::zero()
+    } else if elapsed - config.cliff >= config.duration {
+        total
+    } else {
+        total.multiply_ratio(elapsed - config.cliff, config.duration)
+    };
+    let amount = vested.saturating_sub(position.lp_claimed);
+    if amount.is_zero() {
+        return Err(ContractError::NoLpTokens { });
     }
+    position.lp_claimed += amount;
+    LIQUIDITY_POSITIONS.save(deps.storage, project_id.u128().into(), &position)?;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: lp_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: treasury.wallet.to_string(),
+            amount,
+        })?,
+        funds: Vec::new()
+    };
+    Ok(Response::new()
+    .add_message(msg)
+    .add_event(events::claim(project_id, &treasury.wallet, "lp_tokens", lp_token.as_str(), amount))
+    .add_attribute("action", "Claim LP tokens")
+    .add_attribute("amount", amount.to_string()))
 }
 
-pub fn try_startvesting(deps: DepsMut, _env:Env, info: MessageInfo, project_id: Uint128)
+pub fn try_startvesting(deps: DepsMut, env:Env, info: MessageInfo, project_id: Uint128)
     ->Result<Response, ContractError>
 {
-    let mut x: ProjectInfo = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
-    let y = x.clone();
-    if x.config.token_addr == "".to_string() {
+    let owner = OWNER.load(deps.storage)?;
+    let mut x: ProjectInfo = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    //a second run would reset the sync and wipe the investors on the vesting contract
+    check_allocations_open(deps.storage, project_id)?;
+    if x.config.token_addr.is_empty() {
         return Err(ContractError::NotTokenAddr { });
     }
-    let vesting_addr = VESTING_ADDR.load(deps.storage)?;
-    if vesting_addr == "".to_string() {
-        return Err(ContractError::NotSetVestAddr { });
+    if CANCELLED_PROJECTS.has(deps.storage, project_id.u128().into()) {
+        return Err(ContractError::ProjectCancelled { });
     }
+    let vesting_addr = get_vesting_addr(deps.storage, project_id)?
+        .ok_or(ContractError::NotSetVestAddr { })?;
+    //bonus vesting and the vesting contract both count from the saved start time
     if x.config.start_time == Uint128::zero() {
-        x.config.start_time = Uint128::from(_env.block.time.seconds());
+        x.config.start_time = Uint128::from(env.block.time.seconds());
+        PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
     }
 
     let mut amount = Uint128::zero();
-    for user in x.seed_users{
+    for user in x.seed_users.iter(){
         amount += user.total_amount;
     }
-    for user in x.presale_users{
+    for user in x.presale_users.iter(){
         amount += user.total_amount;
     }
-    for user in x.ido_users{
+    for user in x.ido_users.iter(){
         amount += user.total_amount;
     }
-    // let token_addr = deps.api.addr_validate(x.config.token_addr.as_str())?;
+    // let token_addr = check_addr(deps.api, x.config.token_addr.as_str())?;
     // let token_info: TokenInfoResponse = deps.querier.query_wasm_smart(
     //     token_addr.clone(),
     //     &Cw20QueryMsg::TokenInfo{ }
@@ -119,30 +3062,30 @@ pub fn try_startvesting(deps: DepsMut, _env:Env, info: MessageInfo, project_id:
     // let token_balance: Cw20BalanceResponse = deps.querier.query_wasm_smart(
     //     token_addr,
     //     &Cw20QueryMsg::Balance{
-    //         address: _env.contract.address.to_string(),
+    //         address: env.contract.address.to_string(),
     //     }
     // )?;
     // if token_balance.balance < amount {
     //     return Err(ContractError::NotEnoughBalance { })
     // }
 
-    let msg_vesting = WasmMsg::Execute {
-            contract_addr: vesting_addr.to_string(),
-            msg: to_binary(&vestingExecuteMsg::SetProjectInfo {
-                project_id: project_id,
-                project_info: y
-            }).unwrap(),
-        funds: Vec::new()
-    };
+    let msg_vesting = vesting_setprojectinfo_msg(&vesting_addr, project_id, x.clone())?;
+    VESTING_USER_SYNC.save(deps.storage, project_id.u128().into(), &VestingUserSync{
+        stage: STAGES[0].to_string(),
+        index: 0,
+        finished: false,
+    })?;
 
     Ok(Response::new()
-    .add_message(CosmosMsg::Wasm(msg_vesting))
+    .add_submessage(vesting_submsg(deps.storage, project_id, msg_vesting,
+        VESTING_SET_PROJECT_INFO_REPLY_ID)?)
+    .add_event(events::vesting_started(project_id, x.config.start_time, amount))
     .add_attribute("action", "Start vesting"))
 }
 pub fn try_setvestingparameters(deps: DepsMut, info: MessageInfo, project_id: Uint128, params: Vec<VestingParameter>)
     ->Result<Response, ContractError>
 {
-    let mut x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    let mut x = load_project(deps.storage, project_id)?;
     if x.config.owner != info.sender {
         return Err(ContractError::Unauthorized{ });
     }
@@ -154,134 +3097,331 @@ pub fn try_setvestingparameters(deps: DepsMut, info: MessageInfo, project_id: Ui
     .add_attribute("action", "Set Vesting parameters"))
 }
 
-pub fn check_add_userinfo( users: &mut Vec<UserInfo>, wallet: Addr, amount: Uint128)
+//adds `amount` to the wallet allocation, returning the new allocation
+pub fn check_add_userinfo( users: &mut Vec<UserInfo>, wallet: Addr, amount: Uint128) -> Uint128
 {
     let index =users.iter().position(|x| x.wallet_address == wallet);
-    if index == None {
-        users.push(UserInfo { 
-            wallet_address: wallet, 
-            total_amount: amount, 
-            released_amount: Uint128::zero(), 
-            pending_amount: Uint128::zero() 
-        });
-    }
-    else{
-        users[index.unwrap()].total_amount += amount;
+    match index {
+        None => {
+            users.push(UserInfo { 
+                wallet_address: wallet, 
+                total_amount: amount, 
+                released_amount: Uint128::zero(), 
+                pending_amount: Uint128::zero(),
+                bonus_amount: Uint128::zero(),
+                bonus_released: Uint128::zero(),
+            });
+            amount
+        }
+        Some(index) => {
+            users[index].total_amount += amount;
+            users[index].total_amount
+        }
     }
 }
-pub fn try_addseeduser(deps: DepsMut, info: MessageInfo, project_id: Uint128, wallet:Addr, amount: Uint128)
+pub fn try_addseeduser(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, wallet:Addr, amount: Uint128)
     ->Result<Response, ContractError>
 {
-    let owner = OWNER.load(deps.storage).unwrap();
-    let mut x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    let wallet = check_addr(deps.api, wallet.as_str())?;
+    check_not_denied(deps.storage, project_id, &wallet)?;
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
     if info.sender != owner && info.sender != x.config.owner {
         return Err(ContractError::Unauthorized{ });
     }
+    check_allocations_open(deps.storage, project_id)?;
 
-    check_add_userinfo(&mut x.seed_users, wallet, amount);
+    let allocation = record_allocation(deps.storage, &mut x, "seed", &wallet, amount, Uint128::zero(),
+        env.block.time.seconds())?;
     PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
 
     Ok(Response::new()
+    .add_event(events::allocation_changed(project_id, "seed", &wallet, allocation - amount, allocation,
+        &info.sender))
     .add_attribute("action", "Add  User info for Seed stage"))
 }
-pub fn try_addpresaleuser(deps: DepsMut, info: MessageInfo, project_id: Uint128, wallet: Addr, amount:Uint128)
+pub fn try_addpresaleuser(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, wallet: Addr, amount:Uint128)
     ->Result<Response, ContractError>
 {
-    let owner = OWNER.load(deps.storage).unwrap();
-    let mut x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    let wallet = check_addr(deps.api, wallet.as_str())?;
+    check_not_denied(deps.storage, project_id, &wallet)?;
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
     if info.sender != owner && info.sender != x.config.owner {
         return Err(ContractError::Unauthorized{ });
     }
+    check_allocations_open(deps.storage, project_id)?;
 
-    check_add_userinfo(&mut x.presale_users, wallet, amount);
+    let allocation = record_allocation(deps.storage, &mut x, "presale", &wallet, amount, Uint128::zero(),
+        env.block.time.seconds())?;
     PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
 
     Ok(Response::new()
+    .add_event(events::allocation_changed(project_id, "presale", &wallet, allocation - amount, allocation,
+        &info.sender))
     .add_attribute("action", "Add  User info for Presale stage"))
 }
-pub fn try_addidouser(deps: DepsMut, info: MessageInfo, project_id: Uint128, wallet:Addr, amount:Uint128)
+pub fn try_addidouser(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, wallet:Addr, amount:Uint128)
     ->Result<Response, ContractError>
 {
-    let owner = OWNER.load(deps.storage).unwrap();
-    let mut x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    let wallet = check_addr(deps.api, wallet.as_str())?;
+    check_not_denied(deps.storage, project_id, &wallet)?;
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
     if info.sender != owner && info.sender != x.config.owner {
         return Err(ContractError::Unauthorized{ });
     }
+    check_allocations_open(deps.storage, project_id)?;
 
-    check_add_userinfo(&mut x.ido_users, wallet, amount);
+    let allocation = record_allocation(deps.storage, &mut x, "ido", &wallet, amount, Uint128::zero(),
+        env.block.time.seconds())?;
     PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
 
     Ok(Response::new()
+    .add_event(events::allocation_changed(project_id, "ido", &wallet, allocation - amount, allocation,
+        &info.sender))
     .add_attribute("action", "Add  User info for IDO stage"))
 }
+//allocation changes of replacing the `old` stage users with `new`
+fn replaced_users_events(project_id: Uint128, stage: &str, old: &[UserInfo], new: &[UserInfo],
+    changed_by: &Addr) -> Vec<Event>
+{
+    let amount_in = |users: &[UserInfo], wallet: &Addr| users.iter()
+        .filter(|u| u.wallet_address == *wallet)
+        .map(|u| u.total_amount)
+        .sum::<Uint128>();
+    let mut wallets: Vec<&Addr> = old.iter().chain(new.iter()).map(|u| &u.wallet_address).collect();
+    wallets.sort();
+    wallets.dedup();
+    wallets.into_iter()
+        .map(|wallet| (wallet, amount_in(old, wallet), amount_in(new, wallet)))
+        .filter(|(_, old_amount, new_amount)| old_amount != new_amount)
+        .map(|(wallet, old_amount, new_amount)|
+            events::allocation_changed(project_id, stage, wallet, old_amount, new_amount, changed_by))
+        .collect()
+}
+
 pub fn try_setseedusers(deps: DepsMut, info: MessageInfo, project_id: Uint128, user_infos: Vec<UserInfo>)
     ->Result<Response, ContractError>
 {
-    let owner = OWNER.load(deps.storage).unwrap();
-    let mut x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    for user in user_infos.iter() {
+        check_addr(deps.api, user.wallet_address.as_str())?;
+        check_not_denied(deps.storage, project_id, &user.wallet_address)?;
+    }
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
     if info.sender != owner && info.sender != x.config.owner {
         return Err(ContractError::Unauthorized{ });
     }
+    check_allocations_open(deps.storage, project_id)?;
 
+    let emitted = replaced_users_events(project_id, "seed", &x.seed_users, &user_infos, &info.sender);
     x.seed_users = user_infos;
 
     PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
 
     Ok(Response::new()
+    .add_events(emitted)
     .add_attribute("action", "Set User infos for Seed stage"))
 }
 pub fn try_setpresaleusers(deps: DepsMut, info: MessageInfo, project_id: Uint128, user_infos: Vec<UserInfo>)
     ->Result<Response, ContractError>
 {
-    let owner = OWNER.load(deps.storage).unwrap();
-    let mut x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    for user in user_infos.iter() {
+        check_addr(deps.api, user.wallet_address.as_str())?;
+        check_not_denied(deps.storage, project_id, &user.wallet_address)?;
+    }
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
     if info.sender != owner && info.sender != x.config.owner {
         return Err(ContractError::Unauthorized{ });
     }
+    check_allocations_open(deps.storage, project_id)?;
 
+    let emitted = replaced_users_events(project_id, "presale", &x.presale_users, &user_infos, &info.sender);
     x.presale_users = user_infos;
 
     PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
 
     Ok(Response::new()
+    .add_events(emitted)
     .add_attribute("action", "Set User infos for Presale stage"))
 }
 pub fn try_setidousers(deps: DepsMut, info: MessageInfo, project_id: Uint128, user_infos: Vec<UserInfo>)
     ->Result<Response, ContractError>
 {
-    let owner = OWNER.load(deps.storage).unwrap();
-    let mut x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    for user in user_infos.iter() {
+        check_addr(deps.api, user.wallet_address.as_str())?;
+        check_not_denied(deps.storage, project_id, &user.wallet_address)?;
+    }
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
     if info.sender != owner && info.sender != x.config.owner {
         return Err(ContractError::Unauthorized{ });
     }
+    check_allocations_open(deps.storage, project_id)?;
 
+    let emitted = replaced_users_events(project_id, "ido", &x.ido_users, &user_infos, &info.sender);
     x.ido_users = user_infos;
 
     PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
 
     Ok(Response::new()
+    .add_events(emitted)
     .add_attribute("action", "Set User infos for IDO stage"))
 }
-pub fn try_adduser(deps: DepsMut, info: MessageInfo, project_id: Uint128, wallet: Addr, stage: String, amount: Uint128)
+pub fn try_importusers(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, stage: String,
+    entries: Vec<UserInfo>, mode: ImportMode)
     ->Result<Response, ContractError>
 {
-    let owner = OWNER.load(deps.storage).unwrap();
-    let x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
     if info.sender != owner && info.sender != x.config.owner {
         return Err(ContractError::Unauthorized{ });
     }
-    
-    if stage.to_lowercase() == "seed".to_string(){
-        try_addseeduser(deps, info, project_id, wallet, amount)?;
+    check_allocations_open(deps.storage, project_id)?;
+    let stage = check_stage(&stage)?;
+    if entries.len() > MAX_IMPORT_ENTRIES {
+        return Err(ContractError::TooManyEntries { });
+    }
+
+    let users = stage_users_mut(&mut x, &stage);
+    let previous = users.clone();
+    if mode == ImportMode::Replace {
+        if users.iter().any(|u| !u.released_amount.is_zero() || !u.bonus_released.is_zero()) {
+            return Err(ContractError::AllocationsReleased { });
+        }
+        users.clear();
+    }
+    let existing = users.len();
+
+    let imported = entries.len();
+    let mut amount = Uint128::zero();
+    let mut imported_by: BTreeMap<Addr, Uint128> = BTreeMap::new();
+    for entry in entries {
+        let wallet = check_addr(deps.api, entry.wallet_address.as_str())?;
+        if entry.total_amount.is_zero() || !entry.released_amount.is_zero()
+            || !entry.pending_amount.is_zero() || !entry.bonus_amount.is_zero()
+            || !entry.bonus_released.is_zero() {
+            return Err(ContractError::InvalidImportEntry { wallet: wallet.to_string() });
+        }
+        check_not_denied(deps.storage, project_id, &wallet)?;
+        amount += entry.total_amount;
+        *imported_by.entry(wallet.clone()).or_default() += entry.total_amount;
+        let bonus = stage_bonus(deps.storage, project_id, &stage, env.block.time.seconds(), Uint128::zero(),
+            entry.total_amount)?;
+        check_add_userinfo(users, wallet.clone(), entry.total_amount);
+        check_add_bonus(users, &wallet, bonus);
+    }
+    let added = users.len() - existing;
+    let total: Uint128 = users.iter().map(|u| u.total_amount).sum();
+    let emitted = replaced_users_events(project_id, &stage, &previous, users, &info.sender);
+
+    //imports go through the caps of the stage sale like purchases
+    if let Some(sale) = STAGE_SALES.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))? {
+        let now = env.block.time.seconds();
+        let price = match sale.sale_type {
+            SaleType::FixedPrice => sale.price,
+            SaleType::DutchAuction{ token_cap, .. } => {
+                if total > token_cap {
+                    return Err(ContractError::AllocationExceeded { });
+                }
+                let sold = TOKENS_SOLD.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?
+                    .unwrap_or_default() + amount;
+                if sold > token_cap {
+                    return Err(ContractError::StageSoldOut { });
+                }
+                TOKENS_SOLD.save(deps.storage, (project_id.u128().into(), stage.as_str()), &sold)?;
+                dutch_auction_price(&sale, now)
+            }
+            //their settlement allocates up to the stage cap on its own
+            SaleType::Overflow { .. } | SaleType::BatchAuction { .. } | SaleType::Lottery { .. }
+                => return Err(ContractError::InvalidSaleType { }),
+        };
+        let sale = StageSale{ price, ..sale };
+        for (wallet, tokens) in imported_by.iter() {
+            check_tier_cap(deps.as_ref(), now, project_id, wallet, payment_for_tokens(&sale, *tokens)?)?;
+        }
+    }
+    PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
+
+    Ok(Response::new()
+    .add_events(emitted)
+    .add_attribute("action", "Import users")
+    .add_attribute("stage", stage)
+    .add_attribute("imported", imported.to_string())
+    .add_attribute("added", added.to_string())
+    .add_attribute("merged", (imported - added).to_string())
+    .add_attribute("amount", amount.to_string()))
+}
+//sets the allocation of a stage user, removing it when amount is None
+fn change_allocation(deps: DepsMut, info: MessageInfo, project_id: Uint128, stage: String,
+    wallet: String, amount: Option<Uint128>)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
+    }
+    check_allocations_open(deps.storage, project_id)?;
+    let stage = check_stage(&stage)?;
+    let wallet = check_addr(deps.api, &wallet)?;
+
+    let users = stage_users_mut(&mut x, &stage);
+    let index = users.iter().position(|u| u.wallet_address == wallet)
+        .ok_or(ContractError::UserNotFound { })?;
+    let old_amount = users[index].total_amount;
+    let new_amount = amount.unwrap_or_default();
+    //the bonus follows the allocation, it can't drop below what is already claimed
+    let new_bonus = users[index].bonus_amount.multiply_ratio(new_amount, old_amount);
+    if users[index].bonus_released > new_bonus {
+        return Err(ContractError::AllocationsReleased { });
     }
-    else if stage.to_lowercase() == "presale".to_string(){
-        try_addpresaleuser(deps, info, project_id, wallet, amount)?;
+    match amount {
+        Some(amount) => {
+            users[index].total_amount = amount;
+            users[index].bonus_amount = new_bonus;
+        }
+        None => { users.remove(index); }
     }
-    else if stage.to_lowercase() == "ido".to_string(){
-        try_addidouser(deps, info, project_id, wallet, amount)?;
+    PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &x)?;
+
+    Ok(Response::new()
+    .add_event(events::allocation_changed(project_id, &stage, &wallet, old_amount, new_amount, &info.sender))
+    .add_attribute("action", if amount.is_some() { "Set user allocation" } else { "Remove user" }))
+}
+
+pub fn try_removeuser(deps: DepsMut, info: MessageInfo, project_id: Uint128, stage: String, wallet: String)
+    ->Result<Response, ContractError>
+{
+    change_allocation(deps, info, project_id, stage, wallet, None)
+}
+
+pub fn try_setuserallocation(deps: DepsMut, info: MessageInfo, project_id: Uint128, stage: String,
+    wallet: String, amount: Uint128)
+    ->Result<Response, ContractError>
+{
+    change_allocation(deps, info, project_id, stage, wallet, Some(amount))
+}
+
+pub fn try_adduser(deps: DepsMut, env: Env, info: MessageInfo, project_id: Uint128, wallet: Addr, stage: String, amount: Uint128)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
     }
+    
+    let res = match check_stage(&stage)?.as_str() {
+        "seed" => try_addseeduser(deps, env, info, project_id, wallet, amount)?,
+        "presale" => try_addpresaleuser(deps, env, info, project_id, wallet, amount)?,
+        _ => try_addidouser(deps, env, info, project_id, wallet, amount)?,
+    };
 
     Ok(Response::new()
+    .add_events(res.events)
     .add_attribute("action", "Set User info"))
 }
 
@@ -293,14 +3433,15 @@ pub fn try_setprojectconfig(deps:DepsMut, info:MessageInfo,
 ) -> Result<Response, ContractError>
 {
     //-----------check owner--------------------------
-    let owner = OWNER.load(deps.storage).unwrap();
-    let mut x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    let owner = OWNER.load(deps.storage)?;
+    let mut x = load_project(deps.storage, project_id)?;
     if info.sender != owner && info.sender != x.config.owner {
         return Err(ContractError::Unauthorized{});
     }
 
-    x.config.owner = admin
-        .and_then(|s| deps.api.addr_validate(s.as_str()).ok()).unwrap();
+    if let Some(admin) = admin {
+        x.config.owner = check_addr(deps.api, &admin)?;
+    }
 
     x.config.token_addr = match token_addr{
             Some(v) => v,
@@ -317,33 +3458,59 @@ pub fn try_setprojectconfig(deps:DepsMut, info:MessageInfo,
         .add_attribute("action", "SetConfig"))                                
 }
 
-pub fn try_addproject(deps:DepsMut, info:MessageInfo,
-    project_id: Uint128,
+#[allow(clippy::too_many_arguments)]
+pub fn try_addproject(deps:DepsMut, env:Env, info:MessageInfo,
+    project_id: Option<Uint128>,
     admin: String, 
     token_addr: String,
     vesting_params: Vec<VestingParameter>,
-    start_time: Option<Uint128>
+    start_time: Option<Uint128>,
+    treasury: Option<TreasuryParams>,
+    metadata: Option<ProjectMetadata>
 ) -> Result<Response, ContractError>
 {
     //-----------check owner--------------------------
-    let owner = OWNER.load(deps.storage).unwrap();
+    let owner = OWNER.load(deps.storage)?;
     if info.sender != owner {
         return Err(ContractError::Unauthorized{});
     }
+    let project_id = match project_id {
+        Some(project_id) => {
+            if PROJECT_INFOS.has(deps.storage, project_id.u128().into()) {
+                return Err(ContractError::DuplicateProject { });
+            }
+            project_id
+        },
+        //explicit ids don't move the counter, the next free id after it is taken
+        None => {
+            let mut next = PROJECT_COUNT.may_load(deps.storage)?.unwrap_or_default();
+            loop {
+                next = next.checked_add(Uint128::new(1))
+                    .map_err(|_| ContractError::ProjectIdsExhausted { })?;
+                if !PROJECT_INFOS.has(deps.storage, next.u128().into()) {
+                    break;
+                }
+            }
+            PROJECT_COUNT.save(deps.storage, &next)?;
+            next
+        }
+    };
+    if let Some(metadata) = metadata {
+        check_metadata(&metadata)?;
+        PROJECT_METADATA.save(deps.storage, project_id.u128().into(), &metadata)?;
+    }
 
     let config: Config = Config{
-        owner: deps.api.addr_validate(admin.as_str())?,
-        token_addr: token_addr,
+        owner: check_addr(deps.api, admin.as_str())?,
+        token_addr,
         start_time : match start_time{
             Some(v) => v,
             None => Uint128::zero()
         }
     };
-    let _config = config.clone();
-
     let project_info: ProjectInfo = ProjectInfo{
-        project_id: project_id,
-        config: config,
+        project_id,
+        config,
         vest_param: vesting_params,
         seed_users: Vec::new(),
         presale_users: Vec::new(),
@@ -352,40 +3519,114 @@ pub fn try_addproject(deps:DepsMut, info:MessageInfo,
 
     PROJECT_INFOS.save(deps.storage, project_id.u128().into(), &project_info)?;
 
-    let vesting_addr = VESTING_ADDR.load(deps.storage)?;
-    if vesting_addr != "".to_string() {
-        let msg_addproject = WasmMsg::Execute {
-            contract_addr: vesting_addr.to_string(),
-                msg: to_binary(&vestingExecuteMsg::AddProject {
-                    project_id: project_id,
-                    admin: _config.owner.to_string(), 
-                    token_addr: _config.token_addr, 
-                    start_time: _config.start_time 
-                }).unwrap(),
-            funds: Vec::new()
+    if let Some(treasury) = treasury {
+        let milestones = treasury.milestones.into_iter()
+            .map(|m| Milestone{
+                description: m.description,
+                amount: m.amount,
+                approved: false,
+                released: false,
+            })
+            .collect();
+        TREASURIES.save(deps.storage, project_id.u128().into(), &Treasury{
+            wallet: check_addr(deps.api, &treasury.wallet)?,
+            denom: treasury.denom,
+            total_raised: Uint128::zero(),
+            released: Uint128::zero(),
+            milestones,
+        })?;
+    }
+
+    let event = events::project_added(project_id, &project_info.config.owner,
+        &project_info.config.token_addr);
+
+    //every project gets its own vesting contract when a code id is configured
+    if let Some(code_id) = VESTING_CODE_ID.may_load(deps.storage)? {
+        PENDING_VESTING_PROJECT.save(deps.storage, &project_id)?;
+        let msg_instantiate = WasmMsg::Instantiate {
+            admin: Some(owner.to_string()),
+            code_id,
+            msg: to_binary(&vestingInstantiateMsg {
+                admin: Some(env.contract.address.to_string())
+            })?,
+            funds: Vec::new(),
+            label: format!("vesting-{}", project_id),
         };
         return Ok(Response::new()
-        .add_message(CosmosMsg::Wasm(msg_addproject))
-        .add_attribute("action", "Start vesting"));
+        .add_submessage(SubMsg::reply_on_success(msg_instantiate, INSTANTIATE_VESTING_REPLY_ID))
+        .add_event(event)
+        .add_attribute("action", "Add Project")
+        .add_attribute("project_id", project_id.to_string()));
+    }
+
+    if let Some(vesting_addr) = VESTING_ADDR.may_load(deps.storage)? {
+        let msg_addproject = vesting_addproject_msg(&vesting_addr, project_id, &project_info.config)?;
+        return Ok(Response::new()
+        .add_submessage(vesting_submsg(deps.storage, project_id, msg_addproject,
+            VESTING_ADD_PROJECT_REPLY_ID)?)
+        .add_event(event)
+        .add_attribute("action", "Start vesting")
+        .add_attribute("project_id", project_id.to_string()));
+    }
+
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("action", "Add Project")
+        .add_attribute("project_id", project_id.to_string()))
+}
+
+fn check_metadata(metadata: &ProjectMetadata) -> Result<(), ContractError>
+{
+    let mut uris = metadata.website.iter()
+        .chain(metadata.logo_uri.iter())
+        .chain(metadata.socials.iter().map(|s| &s.url));
+    if metadata.name.is_empty() || metadata.name.len() > MAX_NAME_LENGTH
+        || metadata.description.len() > MAX_DESCRIPTION_LENGTH
+        || metadata.socials.len() > MAX_SOCIAL_LINKS
+        || metadata.socials.iter().any(|s| s.platform.is_empty() || s.platform.len() > MAX_NAME_LENGTH)
+        || uris.any(|uri| uri.is_empty() || uri.len() > MAX_URI_LENGTH) {
+        return Err(ContractError::InvalidMetadata { });
+    }
+    Ok(())
+}
+
+pub fn try_setprojectmetadata(deps: DepsMut, info: MessageInfo, project_id: Uint128,
+    metadata: ProjectMetadata)
+    ->Result<Response, ContractError>
+{
+    let owner = OWNER.load(deps.storage)?;
+    let x = load_project(deps.storage, project_id)?;
+    if info.sender != owner && info.sender != x.config.owner {
+        return Err(ContractError::Unauthorized{ });
     }
+    check_metadata(&metadata)?;
+    PROJECT_METADATA.save(deps.storage, project_id.u128().into(), &metadata)?;
 
     Ok(Response::new()
-        .add_attribute("action", "Add Project"))                                
+    .add_attribute("action", "Set project metadata")
+    .add_attribute("project_id", project_id.to_string()))
 }
-pub fn try_setconfig(deps:DepsMut, env:Env, info:MessageInfo, admin: String, vesting_addr: String) 
+pub fn try_setconfig(deps:DepsMut, info:MessageInfo, admin: String, vesting_addr: Option<String>,
+    vesting_code_id: Option<u64>) 
     -> Result<Response, ContractError>
 {
     //-----------check owner--------------------------
-    // let owner = OWNER.load(deps.storage).unwrap();
-    // if info.sender != owner {
-    //     return Err(ContractError::Unauthorized{});
-    // }
+    let owner = OWNER.load(deps.storage)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
 
-    let admin_addr = deps.api.addr_validate(&admin).unwrap();
+    let admin_addr = check_addr(deps.api, &admin)?;
     OWNER.save(deps.storage, &admin_addr)?;
 
-    let vesting_contract_address = deps.api.addr_validate(&vesting_addr).unwrap();
-    VESTING_ADDR.save(deps.storage, &vesting_contract_address)?;
+    if let Some(vesting_addr) = vesting_addr {
+        let vesting_contract_address = check_addr(deps.api, &vesting_addr)?;
+        VESTING_ADDR.save(deps.storage, &vesting_contract_address)?;
+    }
+
+    if let Some(code_id) = vesting_code_id {
+        VESTING_CODE_ID.save(deps.storage, &code_id)?;
+    }
 
     // let set_vesting_config = WasmMsg::Execute {
     //     contract_addr: vesting_contract_address.to_string(),
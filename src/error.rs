@@ -1,6 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
-use cosmwasm_std::{Uint128};
 
 #[derive(Error, Debug)]
 pub enum ContractError {
@@ -16,14 +15,6 @@ pub enum ContractError {
     #[error("There is no enough tokens")]
     NotEnoughBalance {},
 
-    #[error("Not correct Milestone status : {aust_balance} {estimate_exchange_rate} {epoch_exchange_rate} {withdraw_amount} {release_amount}")]
-    Testing{
-        aust_balance: String,
-        estimate_exchange_rate: String,
-        epoch_exchange_rate: String,
-        withdraw_amount: String,
-        release_amount: String
-    },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 
@@ -35,4 +26,259 @@ pub enum ContractError {
 
     #[error("Not set start time")]
     NotSetStartTime {},
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Vesting contract address not found in instantiate reply")]
+    VestingInstantiateFailed {},
+
+    #[error("Vesting contract is already in sync")]
+    VestingAlreadySynced {},
+
+    #[error("Vesting is not started")]
+    VestingNotStarted {},
+
+    #[error("Vesting is started, allocations can no longer change")]
+    VestingStarted {},
+
+    #[error("Project has no treasury")]
+    NoTreasury {},
+
+    #[error("Must send only {denom}")]
+    InvalidFunds { denom: String },
+
+    #[error("Milestone not found")]
+    MilestoneNotFound {},
+
+    #[error("Milestone is not approved")]
+    MilestoneNotApproved {},
+
+    #[error("Milestone is already released")]
+    MilestoneAlreadyReleased {},
+
+    #[error("Only investors of the project can do this")]
+    NotInvestor {},
+
+    #[error("Project already has an open proposal")]
+    ProposalAlreadyOpen {},
+
+    #[error("Allocations can not change while a proposal is open")]
+    ProposalOpen {},
+
+    #[error("Proposal not found")]
+    ProposalNotFound {},
+
+    #[error("Proposal is not open")]
+    ProposalNotOpen {},
+
+    #[error("Invalid voting config")]
+    InvalidVotingConfig {},
+
+    #[error("Voting period is not over")]
+    VotingNotEnded {},
+
+    #[error("Voting period is over")]
+    VotingEnded {},
+
+    #[error("Already voted")]
+    AlreadyVoted {},
+
+    #[error("Project is wound down")]
+    ProjectWoundDown {},
+
+    #[error("Project is not wound down")]
+    ProjectNotWoundDown {},
+
+    #[error("Refund is already claimed")]
+    RefundAlreadyClaimed {},
+
+    #[error("Money market is not set")]
+    NoMoneyMarket {},
+
+    #[error("Yield shares must sum to one")]
+    InvalidYieldSplit {},
+
+    #[error("There is no yield to claim")]
+    NoYield {},
+
+    #[error("Fee can not exceed 100%")]
+    InvalidFee {},
+
+    #[error("Sale is finalized")]
+    SaleFinalized {},
+
+    #[error("Invalid stage: {stage}")]
+    InvalidStage { stage: String },
+
+    #[error("Stage is not on sale")]
+    StageNotOnSale {},
+
+    #[error("Invalid price")]
+    InvalidPrice {},
+
+    #[error("Contribution is too small to buy any token")]
+    ContributionTooSmall {},
+
+    #[error("Stage is not open")]
+    StageNotOpen {},
+
+    #[error("Stage has not ended")]
+    StageNotEnded {},
+
+    #[error("Stage is already finalized")]
+    StageFinalized {},
+
+    #[error("Stage is not finalized")]
+    StageNotFinalized {},
+
+    #[error("There is no deposit to claim")]
+    NoDeposit {},
+
+    #[error("Not enough tokens left in the stage")]
+    StageSoldOut {},
+
+    #[error("Stage must end after it starts")]
+    InvalidStageWindow {},
+
+    #[error("Stage already holds deposits, bids or tickets")]
+    StageHasEscrow {},
+
+    #[error("Not supported by the sale type of the stage")]
+    InvalidSaleType {},
+
+    #[error("Auction is already settled")]
+    AuctionSettled {},
+
+    #[error("Not whitelisted")]
+    NotWhitelisted {},
+
+    #[error("Too many tickets")]
+    TooManyTickets {},
+
+    #[error("Invalid commitment")]
+    InvalidCommitment {},
+
+    #[error("Seed is already committed")]
+    SeedAlreadyCommitted {},
+
+    #[error("Seed is not committed")]
+    SeedNotCommitted {},
+
+    #[error("Seed is already revealed")]
+    SeedAlreadyRevealed {},
+
+    #[error("Seed does not match the commitment")]
+    InvalidSeed {},
+
+    #[error("Seed is not revealed")]
+    SeedNotRevealed {},
+
+    #[error("Lottery is not drawn")]
+    LotteryNotDrawn {},
+
+    #[error("Seed reveal deadline has passed")]
+    RevealDeadlinePassed {},
+
+    #[error("No staking contract")]
+    NoStakingContract {},
+
+    #[error("Tiers must be sorted by minimum stake")]
+    InvalidTiers {},
+
+    #[error("Contribution exceeds the tier allocation")]
+    AllocationExceeded {},
+
+    #[error("Project has no staking tiers")]
+    NoTiers {},
+
+    #[error("Stake is below the lowest tier")]
+    NotEligible {},
+
+    #[error("Already registered")]
+    AlreadyRegistered {},
+
+    #[error("Registration is closed")]
+    RegistrationClosed {},
+
+    #[error("KYC approval is required")]
+    KycRequired {},
+
+    #[error("KYC approval is expired")]
+    KycExpired {},
+
+    #[error("Invalid KYC signature")]
+    InvalidKycSignature {},
+
+    #[error("KYC approval is revoked")]
+    KycRevoked {},
+
+    #[error("Address {wallet} is denied")]
+    Denied { wallet: String },
+
+    #[error("Can not refer yourself")]
+    SelfReferral {},
+
+    #[error("No referral rewards")]
+    NoReferralRewards {},
+
+    #[error("No bonus to claim")]
+    NoBonus {},
+
+    #[error("Invalid import entry for {wallet}")]
+    InvalidImportEntry { wallet: String },
+
+    #[error("Too many import entries")]
+    TooManyEntries {},
+
+    #[error("Stage allocations are already released")]
+    AllocationsReleased {},
+
+    #[error("User not found")]
+    UserNotFound {},
+
+    #[error("Invalid project metadata")]
+    InvalidMetadata {},
+
+    #[error("Project {project_id} not found")]
+    ProjectNotFound { project_id: Uint128 },
+
+    #[error("Invalid address: {address}")]
+    InvalidAddress { address: String },
+
+    #[error("Project id already exists")]
+    DuplicateProject {},
+
+    #[error("No project id left to assign")]
+    ProjectIdsExhausted {},
+
+    #[error("Project is cancelled")]
+    ProjectCancelled {},
+
+    #[error("Project funds are already released")]
+    FundsReleased {},
+
+    #[error("Project funds took a money market loss")]
+    FundsLost {},
+
+    #[error("Project is not finished")]
+    ProjectNotFinished {},
+
+    #[error("Tokens are not the sale token of the project")]
+    WrongToken {},
+
+    #[error("Sale is not finalized")]
+    SaleNotFinalized {},
+
+    #[error("Liquidity share must be above zero and at most one")]
+    InvalidLiquidityShare {},
+
+    #[error("No liquidity config")]
+    NoLiquidityConfig {},
+
+    #[error("Liquidity is already provided")]
+    LiquidityProvided {},
+
+    #[error("No LP tokens to claim")]
+    NoLpTokens {},
 }
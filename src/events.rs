@@ -0,0 +1,68 @@
+use cosmwasm_std::{Addr, Event, Uint128};
+
+//Typed events for indexers. The chain prefixes each type with `wasm-`, e.g. `wasm-claim`.
+//The attributes below are a stable schema: attributes may be added, but existing ones are
+//never renamed, removed or changed in meaning. Amounts are integer strings in base units.
+//
+//project_added       project_id, admin, token_addr
+//allocation_changed  project_id, stage, wallet, old_amount, new_amount, changed_by
+//                    (new_amount is the wallet's token allocation at the stage after the change)
+//contribution        project_id, stage, wallet, kind, amount, token_amount, total_raised
+//                    kind: treasury | purchase | overflow_deposit | bid | tickets
+//                    (stage is "none" for treasury contributions, token_amount is zero for
+//                    escrowed payments, total_raised is the project total after the contribution)
+//claim               project_id, wallet, kind, asset, amount
+//                    kind: escrow_refund | refund | yield | referral | bonus | lp_tokens
+//                    (asset is the native denom or the cw20 contract address)
+//vesting_started     project_id, start_time, total_amount
+
+pub fn project_added(project_id: Uint128, admin: &Addr, token_addr: &str) -> Event
+{
+    Event::new("project_added")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("admin", admin.to_string())
+        .add_attribute("token_addr", token_addr)
+}
+
+pub fn allocation_changed(project_id: Uint128, stage: &str, wallet: &Addr, old_amount: Uint128,
+    new_amount: Uint128, changed_by: &Addr) -> Event
+{
+    Event::new("allocation_changed")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("stage", stage)
+        .add_attribute("wallet", wallet.to_string())
+        .add_attribute("old_amount", old_amount.to_string())
+        .add_attribute("new_amount", new_amount.to_string())
+        .add_attribute("changed_by", changed_by.to_string())
+}
+
+pub fn contribution(project_id: Uint128, stage: Option<&str>, wallet: &Addr, kind: &str, amount: Uint128,
+    token_amount: Uint128, total_raised: Uint128) -> Event
+{
+    Event::new("contribution")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("stage", stage.unwrap_or("none"))
+        .add_attribute("wallet", wallet.to_string())
+        .add_attribute("kind", kind)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("token_amount", token_amount.to_string())
+        .add_attribute("total_raised", total_raised.to_string())
+}
+
+pub fn claim(project_id: Uint128, wallet: &Addr, kind: &str, asset: &str, amount: Uint128) -> Event
+{
+    Event::new("claim")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("wallet", wallet.to_string())
+        .add_attribute("kind", kind)
+        .add_attribute("asset", asset)
+        .add_attribute("amount", amount.to_string())
+}
+
+pub fn vesting_started(project_id: Uint128, start_time: Uint128, total_amount: Uint128) -> Event
+{
+    Event::new("vesting_started")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("start_time", start_time.to_string())
+        .add_attribute("total_amount", total_amount.to_string())
+}
@@ -1,14 +1,33 @@
+//the package name is not snake case; exempt only the crate name, every module keeps the lint
+#![allow(non_snake_case)]
+
+#[warn(non_snake_case)]
 pub mod contract;
+#[warn(non_snake_case)]
 pub mod query;
+#[warn(non_snake_case)]
 mod error;
+#[warn(non_snake_case)]
 pub mod msg;
+#[warn(non_snake_case)]
 pub mod state;
+#[warn(non_snake_case)]
 pub mod vesting;
+#[warn(non_snake_case)]
+pub mod money_market;
+#[warn(non_snake_case)]
+pub mod staking;
+#[warn(non_snake_case)]
+pub mod dex;
+#[warn(non_snake_case)]
+pub mod events;
 
 pub use crate::error::ContractError;
 
 #[cfg(test)]
+#[warn(non_snake_case)]
 mod test;
 
-// #[cfg(test)]
-// mod mock_querier;
+#[cfg(test)]
+#[warn(non_snake_case)]
+mod mock_querier;
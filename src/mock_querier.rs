@@ -1,18 +1,15 @@
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
-
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Coin, ContractResult, Decimal, OwnedDeps, Querier,
+    from_binary, from_slice, to_binary, Addr, Coin, ContractResult, Decimal, Empty, OwnedDeps, Querier,
     QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
 use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg};
 
 use std::collections::HashMap;
-use std::marker::PhantomData;
 
-use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
-use terraswap::asset::{AssetInfo, PairInfo};
+use crate::money_market::{QueryMsg as MarketQueryMsg, ExchangeRateResponse};
+use crate::staking::{QueryMsg as StakingQueryMsg, StakedResponse};
+use crate::dex::{FactoryQueryMsg, PairInfo};
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
 /// this uses our CustomQuerier.
@@ -26,15 +23,15 @@ pub fn mock_dependencies(
         api: MockApi::default(),
         storage: MockStorage::default(),
         querier: custom_querier,
-        custom_query_type: PhantomData
     }
 }
 
 pub struct WasmMockQuerier {
-    base: MockQuerier<TerraQueryWrapper>,
+    base: MockQuerier<Empty>,
     token_querier: TokenQuerier,
-    tax_querier: TaxQuerier,
-    terraswap_factory_querier: TerraswapFactoryQuerier,
+    market_querier: MarketQuerier,
+    staking_querier: StakingQuerier,
+    factory_querier: FactoryQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -66,55 +63,34 @@ pub(crate) fn balances_to_map(
     balances_map
 }
 
-#[derive(Clone, Default)]
-pub struct TaxQuerier {
-    rate: Decimal,
-    // this lets us iterate over all pairs that match the first string
-    caps: HashMap<String, Uint128>,
+#[derive(Clone)]
+pub struct MarketQuerier {
+    exchange_rate: Decimal,
 }
 
-impl TaxQuerier {
-    pub fn new(rate: Decimal, caps: &[(&String, &Uint128)]) -> Self {
-        TaxQuerier {
-            rate,
-            caps: caps_to_map(caps),
+impl Default for MarketQuerier {
+    fn default() -> Self {
+        MarketQuerier {
+            exchange_rate: Decimal::one(),
         }
     }
 }
 
-pub(crate) fn caps_to_map(caps: &[(&String, &Uint128)]) -> HashMap<String, Uint128> {
-    let mut owner_map: HashMap<String, Uint128> = HashMap::new();
-    for (denom, cap) in caps.iter() {
-        owner_map.insert(denom.to_string(), **cap);
-    }
-    owner_map
+#[derive(Clone, Default)]
+pub struct StakingQuerier {
+    // stake of each address, the same at every height
+    stakes: HashMap<String, Uint128>,
 }
 
 #[derive(Clone, Default)]
-pub struct TerraswapFactoryQuerier {
+pub struct FactoryQuerier {
+    // pair address keyed by the concatenated asset infos
     pairs: HashMap<String, String>,
 }
 
-impl TerraswapFactoryQuerier {
-    pub fn new(pairs: &[(&String, &String)]) -> Self {
-        TerraswapFactoryQuerier {
-            pairs: pairs_to_map(pairs),
-        }
-    }
-}
-
-pub(crate) fn pairs_to_map(pairs: &[(&String, &String)]) -> HashMap<String, String> {
-    let mut pairs_map: HashMap<String, String> = HashMap::new();
-    for (key, pair) in pairs.iter() {
-        pairs_map.insert(key.to_string(), pair.to_string());
-    }
-    pairs_map
-}
-
 impl Querier for WasmMockQuerier {
     fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
-        // MockQuerier doesn't support Custom, so we ignore it completely here
-        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
             Ok(v) => v,
             Err(e) => {
                 return SystemResult::Err(SystemError::InvalidRequest {
@@ -127,63 +103,36 @@ impl Querier for WasmMockQuerier {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum QueryMsg {
-    Pair { asset_infos: [AssetInfo; 2] },
-}
-
 impl WasmMockQuerier {
-    pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
-            QueryRequest::Custom(TerraQueryWrapper { route, query_data }) => {
-                if route == &TerraRoute::Treasury {
-                    match query_data {
-                        TerraQuery::TaxRate {} => {
-                            let res = TaxRateResponse {
-                                rate: self.tax_querier.rate,
-                            };
-                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
-                        }
-                        TerraQuery::TaxCap { denom } => {
-                            let cap = self
-                                .tax_querier
-                                .caps
-                                .get(denom)
-                                .copied()
-                                .unwrap_or_default();
-                            let res = TaxCapResponse { cap };
-                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
-                        }
-                        _ => panic!("DO NOT ENTER HERE"),
-                    }
-                } else {
-                    panic!("DO NOT ENTER HERE")
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                if let Ok(MarketQueryMsg::ExchangeRate {}) = from_binary(msg) {
+                    let res = ExchangeRateResponse {
+                        exchange_rate: self.market_querier.exchange_rate,
+                    };
+                    return SystemResult::Ok(ContractResult::from(to_binary(&res)));
                 }
-            }
-            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => match from_binary(msg) {
-                Ok(QueryMsg::Pair { asset_infos }) => {
+                if let Ok(StakingQueryMsg::StakedAt { address, .. }) = from_binary(msg) {
+                    let res = StakedResponse {
+                        stake: self.staking_querier.stakes.get(&address).cloned().unwrap_or_default(),
+                    };
+                    return SystemResult::Ok(ContractResult::from(to_binary(&res)));
+                }
+                if let Ok(FactoryQueryMsg::Pair { asset_infos }) = from_binary(msg) {
                     let key = asset_infos[0].to_string() + asset_infos[1].to_string().as_str();
-                    match self.terraswap_factory_querier.pairs.get(&key) {
-                        Some(v) => SystemResult::Ok(ContractResult::from(to_binary(&PairInfo {
-                            contract_addr: v.to_string(),
-                            liquidity_token: "liquidity".to_string(),
-                            asset_infos: [
-                                AssetInfo::NativeToken {
-                                    denom: "uusd".to_string(),
-                                },
-                                AssetInfo::NativeToken {
-                                    denom: "uusd".to_string(),
-                                },
-                            ],
+                    return match self.factory_querier.pairs.get(&key) {
+                        Some(pair) => SystemResult::Ok(ContractResult::from(to_binary(&PairInfo {
+                            asset_infos,
+                            contract_addr: Addr::unchecked(pair),
+                            liquidity_token: Addr::unchecked("liquidity"),
                         }))),
-                        None => SystemResult::Err(SystemError::InvalidRequest {
-                            error: "No pair info exists".to_string(),
-                            request: msg.as_slice().into(),
-                        }),
-                    }
+                        None => SystemResult::Ok(ContractResult::Err(
+                            "PairInfo not found".to_string(),
+                        )),
+                    };
                 }
-                _ => match from_binary(msg).unwrap() {
+                match from_binary(msg).unwrap() {
                     Cw20QueryMsg::Balance { address } => {
                         let balances: &HashMap<String, Uint128> =
                             match self.token_querier.balances.get(contract_addr) {
@@ -216,20 +165,21 @@ impl WasmMockQuerier {
                         ))
                     }
                     _ => panic!("DO NOT ENTER HERE"),
-                },
-            },
+                }
+            }
             _ => self.base.handle_query(request),
         }
     }
 }
 
 impl WasmMockQuerier {
-    pub fn new(base: MockQuerier<TerraQueryWrapper>) -> Self {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
         WasmMockQuerier {
             base,
             token_querier: TokenQuerier::default(),
-            tax_querier: TaxQuerier::default(),
-            terraswap_factory_querier: TerraswapFactoryQuerier::default(),
+            market_querier: MarketQuerier::default(),
+            staking_querier: StakingQuerier::default(),
+            factory_querier: FactoryQuerier::default(),
         }
     }
 
@@ -238,13 +188,22 @@ impl WasmMockQuerier {
         self.token_querier = TokenQuerier::new(balances);
     }
 
-    // configure the token owner mock querier
-    pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
-        self.tax_querier = TaxQuerier::new(rate, caps);
+    // configure the money market exchange rate
+    pub fn with_exchange_rate(&mut self, exchange_rate: Decimal) {
+        self.market_querier = MarketQuerier { exchange_rate };
+    }
+
+    // configure the stakes reported by the staking contract
+    pub fn with_stakes(&mut self, stakes: &[(&str, u128)]) {
+        self.staking_querier = StakingQuerier {
+            stakes: stakes.iter().map(|(addr, stake)| (addr.to_string(), Uint128::new(*stake))).collect(),
+        };
     }
 
-    // configure the terraswap pair
-    pub fn with_terraswap_pairs(&mut self, pairs: &[(&String, &String)]) {
-        self.terraswap_factory_querier = TerraswapFactoryQuerier::new(pairs);
+    // configure the pairs of the dex factory, keyed by the concatenated asset infos
+    pub fn with_dex_pairs(&mut self, pairs: &[(&str, &str)]) {
+        self.factory_querier = FactoryQuerier {
+            pairs: pairs.iter().map(|(key, pair)| (key.to_string(), pair.to_string())).collect(),
+        };
     }
 }
@@ -0,0 +1,26 @@
+use cosmwasm_std::{Uint128, Decimal};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+//Adapter interface of the money-market contract holding idle treasury funds.
+//Deposits are tracked as shares, worth `shares * exchange_rate` stable coins.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    Deposit {},
+    Redeem {
+        shares: Uint128
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    ExchangeRate {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateResponse {
+    pub exchange_rate: Decimal,
+}
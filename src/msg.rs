@@ -1,10 +1,12 @@
-use cosmwasm_std::{Uint128, Addr};
+use cosmwasm_std::{Uint128, Addr, Decimal, Binary};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub admin: Option<String>,
+    pub vesting_code_id: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -12,14 +14,21 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     SetConfig {
         admin: String,
-        vesting_addr: String,
+        vesting_addr: Option<String>,
+        vesting_code_id: Option<u64>,
     },
     AddProject {
-        project_id: Uint128,
+        project_id: Option<Uint128>, //next free id when unset
         admin: String, 
         token_addr: String,
         vesting_params: Vec<VestingParameter>,
         start_time: Option<Uint128>,
+        treasury: Option<TreasuryParams>,
+        metadata: Option<ProjectMetadata>,
+    },
+    SetProjectMetadata {
+        project_id: Uint128,
+        metadata: ProjectMetadata
     },
     SetProjectConfig { 
         project_id: Uint128,
@@ -64,19 +73,275 @@ pub enum ExecuteMsg {
         wallet: Addr,
         amount: Uint128
     },
+    ImportUsers {
+        project_id: Uint128,
+        stage: String,
+        entries: Vec<UserInfo>,
+        mode: ImportMode
+    },
+    RemoveUser {
+        project_id: Uint128,
+        stage: String,
+        wallet: String
+    },
+    SetUserAllocation {
+        project_id: Uint128,
+        stage: String,
+        wallet: String,
+        amount: Uint128
+    },
     StartVesting {
         project_id: Uint128
+    },
+    RetryVestingSync {
+        project_id: Uint128
+    },
+    SyncVestingUsers {
+        project_id: Uint128,
+        limit: Option<u32>
+    },
+    Contribute {
+        project_id: Uint128,
+        stage: Option<String>,
+        kyc: Option<KycProof>,
+        referrer: Option<String>
+    },
+    ApproveMilestone {
+        project_id: Uint128,
+        milestone_index: u32
+    },
+    ReleaseMilestone {
+        project_id: Uint128,
+        milestone_index: u32
+    },
+    SetVotingConfig {
+        quorum: Decimal,
+        threshold: Decimal,
+        voting_period: u64
+    },
+    ProposeWindDown {
+        project_id: Uint128,
+        description: String
+    },
+    Vote {
+        project_id: Uint128,
+        proposal_id: u64,
+        wind_down: bool
+    },
+    EndProposal {
+        project_id: Uint128,
+        proposal_id: u64
+    },
+    ClaimRefund {
+        project_id: Uint128
+    },
+    CancelProject {
+        project_id: Uint128
+    },
+    ArchiveProject {
+        project_id: Uint128,
+        archived: bool
+    },
+    Receive(Cw20ReceiveMsg), //sale tokens sent with a Cw20HookMsg
+    SetMoneyMarket {
+        market_addr: String,
+        platform_wallet: String,
+        platform_share: Decimal,
+        project_share: Decimal,
+        investor_share: Decimal
+    },
+    DepositIdleFunds {
+        project_id: Uint128,
+        amount: Uint128
+    },
+    RedeemIdleFunds {
+        project_id: Uint128,
+        shares: Option<Uint128>
+    },
+    ClaimYield {
+        project_id: Uint128,
+        kyc: Option<KycProof>
+    },
+    SetFeeConfig {
+        collector: String,
+        raise_fee: Decimal,
+        token_fee: Decimal
+    },
+    SetProjectFees {
+        project_id: Uint128,
+        fees: Option<FeeRates>
+    },
+    FinalizeSale {
+        project_id: Uint128
+    },
+    SetStageSale {
+        project_id: Uint128,
+        stage: String,
+        sale: StageSale
+    },
+    FinalizeStage {
+        project_id: Uint128,
+        stage: String
+    },
+    ClaimAllocationAndRefund {
+        project_id: Uint128,
+        stage: String,
+        kyc: Option<KycProof>
+    },
+    PlaceBid {
+        project_id: Uint128,
+        stage: String,
+        max_price: Decimal,
+        kyc: Option<KycProof>
+    },
+    SettleAuction {
+        project_id: Uint128,
+        stage: String,
+        limit: Option<u32>
+    },
+    SetLotteryWhitelist {
+        project_id: Uint128,
+        stage: String,
+        entries: Vec<WhitelistEntry>
+    },
+    RegisterTickets {
+        project_id: Uint128,
+        stage: String,
+        tickets: u32,
+        kyc: Option<KycProof>
+    },
+    CommitLotterySeed {
+        project_id: Uint128,
+        stage: String,
+        commitment: Binary //sha256 of the seed
+    },
+    RevealLotterySeed {
+        project_id: Uint128,
+        stage: String,
+        seed: Binary
+    },
+    DrawLottery {
+        project_id: Uint128,
+        stage: String,
+        limit: Option<u32>
+    },
+    SetStakingContract {
+        staking_addr: String
+    },
+    SetProjectTiers {
+        project_id: Uint128,
+        tiers: Option<ProjectTiers>
+    },
+    SetRegistration {
+        project_id: Uint128,
+        registration: Option<RegistrationConfig>
+    },
+    Register {
+        project_id: Uint128
+    },
+    SetKycConfig {
+        kyc: Option<KycConfig>
+    },
+    SetKycRevoked {
+        wallets: Vec<String>,
+        revoked: bool
+    },
+    SetCompliance {
+        compliance: String
+    },
+    SetDenied {
+        project_id: Option<Uint128>,
+        wallets: Vec<String>,
+        denied: bool
+    },
+    SetReferralRates {
+        project_id: Uint128,
+        rates: Option<ReferralRates>
+    },
+    SetBonusRules {
+        project_id: Uint128,
+        stage: String,
+        rules: Option<BonusRules>
+    },
+    ClaimBonus {
+        project_id: Uint128,
+        kyc: Option<KycProof>
+    },
+    ClaimReferralRewards {
+        project_id: Uint128,
+        kyc: Option<KycProof>
+    },
+    SetLiquidityConfig {
+        project_id: Uint128,
+        liquidity: Option<LiquidityConfig>
+    },
+    BootstrapLiquidity {
+        project_id: Uint128
+    },
+    ClaimLpTokens {
+        project_id: Uint128
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    DepositTokens {
+        project_id: Uint128
+    },
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetConfig{ project_id: Uint128 },
     GetProjectInfo{ project_id: Uint128 },
-    GetAllProjectInfo { },
+    GetAllProjectInfo { }, //projects that are not archived
+    GetArchivedProjects { },
+    IsCancelled{ project_id: Uint128 },
+    GetProjectTokens{ project_id: Uint128 }, //deposited sale tokens not paid out yet
+    GetProjectMetadata{ project_id: Uint128 },
+    GetProjectCount{ }, //last id given to a project added without one
     GetBalance{ project_id: Uint128, wallet: String },
     GetOwner{ },
+    GetVestingAddr{ project_id: Uint128 },
+    GetVestingSyncStatus{ project_id: Uint128 },
+    GetVestingUserSync{ project_id: Uint128 },
+    GetTreasury{ project_id: Uint128 },
+    GetContribution{ project_id: Uint128, wallet: String },
+    GetVotingConfig{ },
+    GetProposal{ project_id: Uint128, proposal_id: u64 },
+    GetAllProposals{ project_id: Uint128 },
+    GetWindDown{ project_id: Uint128 },
+    GetMoneyMarket{ },
+    GetMarketPosition{ project_id: Uint128 },
+    GetFeeConfig{ },
+    GetFees{ project_id: Uint128 },
+    GetStageSale{ project_id: Uint128, stage: String },
+    SimulateContribution{ project_id: Uint128, stage: String, amount: Uint128 },
+    GetOverflowStatus{ project_id: Uint128, stage: String },
+    GetOverflowDeposit{ project_id: Uint128, stage: String, wallet: String },
+    GetCurrentPrice{ project_id: Uint128, stage: String },
+    GetAuctionState{ project_id: Uint128, stage: String },
+    GetBids{ project_id: Uint128, stage: String, start_after: Option<Binary>, limit: Option<u32> },
+    GetLottery{ project_id: Uint128, stage: String },
+    GetLotteryEntry{ project_id: Uint128, stage: String, wallet: String },
+    GetStakingContract{ },
+    GetProjectTiers{ project_id: Uint128 },
+    GetTierAllocation{ project_id: Uint128, wallet: String },
+    GetRegistration{ project_id: Uint128 },
+    GetKycConfig{ },
+    IsKycRevoked{ wallet: String },
+    GetCompliance{ },
+    IsDenied{ project_id: Uint128, wallet: String },
+    GetDenyList{ project_id: Option<Uint128>, start_after: Option<String>, limit: Option<u32> },
+    GetReferralRates{ project_id: Uint128 },
+    GetReferralRewards{ project_id: Uint128, referrer: String },
+    GetTopReferrers{ project_id: Uint128, limit: Option<u32> },
+    GetBonusRules{ project_id: Uint128, stage: String },
+    GetBonus{ project_id: Uint128, wallet: String },
+    GetLiquidityConfig{ project_id: Uint128 },
+    GetLiquidityPosition{ project_id: Uint128 },
 }
 
 //------------Config---------------------------------------
@@ -87,6 +352,22 @@ pub struct Config {
 	pub start_time: Uint128,
 }
 
+//------------Metadata---------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SocialLink{
+	pub platform: String,
+	pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProjectMetadata{
+	pub name: String,
+	pub description: String,
+	pub website: Option<String>,
+	pub logo_uri: Option<String>,
+	pub socials: Vec<SocialLink>,
+}
+
 //------------Vesting parameter---------------------------------------
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Copy)]
 pub struct VestingParameter{
@@ -102,6 +383,10 @@ pub struct UserInfo{
 	pub total_amount: Uint128, //WFD token total amount that the investor buys.
 	pub released_amount: Uint128, //released WFD token amount of totalAmount
 	pub pending_amount: Uint128, //token amount that investor can claim 
+	#[serde(default)]
+	pub bonus_amount: Uint128, //bonus tokens, vested by this contract on the stage bonus schedule
+	#[serde(default)]
+	pub bonus_released: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -113,3 +398,440 @@ pub struct ProjectInfo{
 	pub presale_users: Vec<UserInfo>,
 	pub ido_users: Vec<UserInfo>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode{
+	Append, //entries are merged into the stage users
+	Replace, //entries replace the stage users, only before anything is released
+}
+
+//-------------Vesting contract sync----------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState{
+	#[default]
+	NotSent,
+	Success,
+	Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct VestingSyncStatus{
+	pub add_project: SyncState, //AddProject registration on the vesting contract
+	pub set_project_info: SyncState, //SetProjectInfo sent by StartVesting
+	pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingUserSync{
+	pub stage: String, //stage being pushed to the vesting contract
+	pub index: u32, //next investor of the stage to push
+	pub finished: bool,
+}
+
+//-------------Treasury-----------------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MilestoneParam{
+	pub description: String,
+	pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryParams{
+	pub wallet: String, //project wallet receiving released funds
+	pub denom: String, //native coin accepted for contributions
+	pub milestones: Vec<MilestoneParam>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Milestone{
+	pub description: String,
+	pub amount: Uint128,
+	pub approved: bool,
+	pub released: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Treasury{
+	pub wallet: Addr,
+	pub denom: String,
+	pub total_raised: Uint128, //all contributions received
+	pub released: Uint128, //paid out to the project wallet and fee collector
+	pub milestones: Vec<Milestone>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryResponse{
+	pub treasury: Treasury,
+	pub locked: Uint128, //raised funds still held by the contract
+}
+
+//-------------Investor votes-----------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VotingConfig{
+	pub quorum: Decimal, //share of total investor weight that must vote
+	pub threshold: Decimal, //share of cast weight needed to wind down
+	pub voting_period: u64, //seconds
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus{
+	Open,
+	Passed,
+	Rejected,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Proposal{
+	pub id: u64,
+	pub description: String,
+	pub proposer: Addr,
+	pub end_time: u64,
+	pub yes_weight: Uint128, //weight voting to wind down
+	pub no_weight: Uint128, //weight voting to continue
+	pub total_weight: Uint128, //investor weight when the proposal was created
+	pub status: ProposalStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WindDown{
+	pub proposal_id: u64,
+	pub fund_pool: Uint128, //escrowed treasury funds to refund
+	pub token_pool: Uint128, //unreleased sale tokens to return
+	pub total_weight: Uint128,
+}
+
+//-------------Money market-------------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MoneyMarketConfig{
+	pub market_addr: Addr,
+	pub platform_wallet: Addr,
+	pub platform_share: Decimal, //yield split, the three shares sum to one
+	pub project_share: Decimal,
+	pub investor_share: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct MarketPosition{
+	pub principal: Uint128, //treasury funds currently deposited
+	pub shares: Uint128, //market shares held for the project
+	pub investor_yield: Uint128, //yield kept for contributors, claimed pro-rata
+	#[serde(default)]
+	pub yield_index: Decimal, //investor yield earned per unit contributed
+	#[serde(default)]
+	pub losses: Uint128, //market losses taken off the raised funds
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct YieldCheckpoint{
+	pub index: Decimal, //yield index the contribution was last settled at
+	pub pending: Uint128, //settled yield not claimed yet
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketPositionResponse{
+	pub position: MarketPosition,
+	pub value: Uint128, //current value of the shares
+}
+
+//-------------Platform fees------------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeRates{
+	pub raise_fee: Decimal, //share of raised funds
+	pub token_fee: Decimal, //share of sold tokens
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeConfig{
+	pub collector: Addr,
+	pub rates: FeeRates,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PaidFees{
+	pub funds: Uint128,
+	pub tokens: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeesResponse{
+	pub rates: FeeRates, //project override or the platform rates
+	pub accrued_funds: Uint128, //owed on the current raise, not paid yet
+	pub accrued_tokens: Uint128,
+	pub paid: PaidFees,
+}
+
+//-------------Stage sale---------------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StageSale{
+	pub price: Decimal, //payment coins per whole sale token
+	pub payment_decimals: u8,
+	pub token_decimals: u8,
+	#[serde(default)]
+	pub sale_type: SaleType,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SaleType{
+	//tokens are allocated as soon as the payment is received
+	#[default]
+	FixedPrice,
+	//deposits are collected in the window and accepted pro-rata up to the cap
+	Overflow { cap: Uint128, start_time: u64, end_time: u64 },
+	//price declines linearly from start_price to the stage price over the window,
+	//bids lock in the current price until token_cap tokens are sold
+	DutchAuction { start_price: Decimal, start_time: u64, end_time: u64, token_cap: Uint128 },
+	//escrowed bids with a max price, settled at one clearing price for token_cap tokens,
+	//the stage price is the reserve price
+	BatchAuction { start_time: u64, end_time: u64, token_cap: Uint128 },
+	//whitelisted users escrow ticket_price per ticket in the window,
+	//winning_tickets are drawn from a committed seed and the rest refunded,
+	//all tickets are refunded when the seed is not revealed in time
+	Lottery { ticket_price: Uint128, start_time: u64, end_time: u64, winning_tickets: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bid{
+	pub id: u64,
+	pub bidder: Addr,
+	pub amount: Uint128, //escrowed payment
+	pub max_price: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionPhase{
+	Bidding,
+	Pricing, //walking bids from the highest price to find the clearing price
+	Filling, //walking bids again to write allocations and refunds
+	Settled,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionState{
+	pub phase: AuctionPhase,
+	pub bid_count: u64,
+	pub cursor: Option<Binary>, //key of the last processed bid
+	pub group_price: Decimal, //max price of the bids being accumulated
+	pub demand_before: Uint128, //payment of bids above group_price
+	pub demand: Uint128, //payment of bids at or above group_price
+	pub clearing_price: Decimal,
+	pub marginal_price: Option<Decimal>, //bids at this price are filled pro-rata
+	pub marginal_fill: Uint128, //tokens left for the marginal bids
+	pub marginal_demand: Uint128, //tokens asked by the marginal bids
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidsResponse{
+	pub bids: Vec<(Binary, Bid)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrentPriceResponse{
+	pub price: Decimal,
+	pub tokens_sold: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct OverflowStatus{
+	pub total_deposits: Uint128,
+	pub finalized: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateContributionResponse{
+	pub token_amount: Uint128, //sale tokens bought, rounded down
+	pub bonus_amount: Uint128, //bonus tokens if bought now
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistEntry{
+	pub wallet: String,
+	pub max_tickets: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct LotteryEntry{
+	pub max_tickets: u32,
+	pub tickets: u32, //registered
+	pub won: u32,
+	pub refunded: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct LotteryState{
+	pub commitment: Option<Binary>,
+	pub ticket_count: u64,
+	pub randomness: Option<Binary>, //sha256(seed | block height | block time), set on reveal
+	pub drawn: u64,
+}
+
+//-------------Staking tiers------------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TierAllocation{
+	Guaranteed { amount: Uint128 },
+	Multiplier { multiplier: Decimal }, //of the project base_allocation
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Tier{
+	pub min_stake: Uint128,
+	pub allocation: TierAllocation,
+	#[serde(default)]
+	pub weight: Uint128, //share of the registration allocation
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProjectTiers{
+	pub snapshot_height: u64, //stakes are read at this block
+	pub base_allocation: Uint128,
+	pub tiers: Vec<Tier>, //ascending min_stake
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TierAllocationResponse{
+	pub stake: Uint128, //recorded at registration if the project has one
+	pub weight: Uint128,
+	pub cap: Uint128, //payment a wallet can spend on stage sales, zero below the first tier
+	pub contributed: Uint128,
+}
+
+//users register in the window and share total_allocation by tier weight once it closes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegistrationConfig{
+	pub start_time: u64,
+	pub end_time: u64,
+	pub total_allocation: Uint128, //payment, split across registered users
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Registrant{
+	pub stake: Uint128,
+	pub weight: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegistrationResponse{
+	pub config: Option<RegistrationConfig>,
+	pub total_weight: Uint128,
+	pub registrants: u64,
+}
+
+//-------------KYC------------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KycScheme{
+	Secp256k1, //signature over sha256 of the payload
+	Ed25519, //signature over the payload
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct KycConfig{
+	pub scheme: KycScheme,
+	pub pubkey: Binary,
+}
+
+//provider approval of a wallet for a project, valid until expiry (seconds)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct KycProof{
+	pub expiry: u64,
+	pub signature: Binary,
+}
+
+//signed payload is the JSON encoding of this struct
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct KycPayload{
+	pub contract: String,
+	pub project_id: Uint128,
+	pub wallet: String,
+	pub expiry: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenyListResponse{
+	pub wallets: Vec<Addr>,
+}
+
+//-------------Referrals------------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralRates{
+	pub payment_rate: Decimal, //share of the referred payment
+	pub token_rate: Decimal, //share of the referred sale tokens
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ReferralRewards{
+	pub referred: Uint128, //payment brought in by the referrer
+	pub funds: Uint128,
+	pub tokens: Uint128,
+	pub claimed_funds: Uint128,
+	pub claimed_tokens: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TopReferrersResponse{
+	pub referrers: Vec<(Addr, ReferralRewards)>, //by referred payment, highest first
+}
+
+//-------------Bonus------------------------------------------
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TimeBonus{
+	pub until: u64, //purchases before this time
+	pub rate: Decimal, //of the tokens bought
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AmountBonus{
+	pub min_payment: Uint128,
+	pub rate: Decimal, //of the tokens bought
+}
+
+//Bonus of an allocation recorded in the stage, whatever its sale type, is the first
+//matching time tier rate plus the highest matching amount tier rate, times the tokens
+//allocated. Admin adds and imports count as a zero payment for the amount tiers.
+//It vests linearly over duration after cliff, from the project start time.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BonusRules{
+	pub time_tiers: Vec<TimeBonus>, //ascending until
+	pub amount_tiers: Vec<AmountBonus>, //ascending min_payment
+	pub cliff: u64,
+	pub duration: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BonusResponse{
+	pub total: Uint128,
+	pub released: Uint128,
+	pub claimable: Uint128,
+}
+
+//-------------Liquidity------------------------------------------
+//After the sale is finalized, funds_share of the raised funds and the tokens they buy at
+//price are provided to the factory pair. The LP tokens vest to the project wallet
+//linearly over duration after cliff, from the time liquidity is provided.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidityConfig{
+	pub factory: String,
+	pub funds_share: Decimal, //of total raised
+	pub price: Decimal, //payment coins per whole token in the pool
+	pub payment_decimals: u8,
+	pub token_decimals: u8,
+	pub cliff: u64,
+	pub duration: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidityPosition{
+	pub pair: Option<Addr>, //unset while the pair is being created
+	pub lp_token: Option<Addr>,
+	pub funds: Uint128,
+	pub tokens: Uint128,
+	pub provided_at: u64,
+	pub lp_claimed: Uint128,
+	#[serde(default)]
+	pub lp_amount: Uint128, //LP tokens received by the deposit of this project
+}
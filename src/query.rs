@@ -1,15 +1,29 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, Env, StdResult,
+    to_binary, Binary, Deps, Env, StdResult, Order,
     Uint128, QueryRequest, BankQuery,
-    Coin, AllBalanceResponse,
+    Coin, AllBalanceResponse, Addr, StdError,
 };
 
+use cw_storage_plus::Bound;
 use cw20::{ Cw20QueryMsg, BalanceResponse as Cw20BalanceResponse, TokenInfoResponse };
 
-use crate::msg::{QueryMsg, Config, ProjectInfo};
-use crate::state::{PROJECT_INFOS, OWNER};
+use crate::msg::{QueryMsg, Config, ProjectInfo, VestingSyncStatus, TreasuryResponse, Proposal,
+    MarketPositionResponse, StageSale, SimulateContributionResponse, CurrentPriceResponse,
+    BidsResponse, RegistrationResponse, DenyListResponse, TopReferrersResponse};
+use crate::state::{PROJECT_INFOS, OWNER, VESTING_SYNC, VESTING_USER_SYNC, TREASURIES, CONTRIBUTIONS,
+    VOTING_CONFIG, PROPOSALS, WIND_DOWNS, MONEY_MARKET, MARKET_POSITIONS, FEE_CONFIG,
+    STAGE_SALES, OVERFLOW_STATUS, OVERFLOW_DEPOSITS, TOKENS_SOLD, AUCTION_STATES, BIDS,
+    LOTTERIES, LOTTERY_ENTRIES, STAKING_CONTRACT, PROJECT_TIERS,
+    REGISTRATIONS, REGISTERED_WEIGHT, KYC_CONFIG, KYC_REVOKED,
+    COMPLIANCE, DENY_LIST, PROJECT_DENY_LIST, REFERRAL_RATES, REFERRAL_REWARDS, REFERRER_RANKS,
+    BONUS_RULES, LIQUIDITY_CONFIGS, LIQUIDITY_POSITIONS, CANCELLED_PROJECTS, ARCHIVED_PROJECTS,
+    PROJECT_COUNT, PROJECT_METADATA, PROJECT_TOKENS};
+use crate::money_market::{QueryMsg as MarketQueryMsg, ExchangeRateResponse};
+use crate::contract::{get_vesting_addr, default_voting_config, project_fees, tokens_for_payment,
+    dutch_auction_price, new_auction_state, tier_allocation,
+    is_denied, stage_bonus, bonus_of};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
@@ -26,27 +40,192 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::GetAllProjectInfo{ } =>
             to_binary(&query_getallprojectinfo(deps)?),
 
+        QueryMsg::GetArchivedProjects{ } =>
+            to_binary(&query_archivedprojects(deps)?),
+
+        QueryMsg::GetProjectMetadata{ project_id } =>
+            to_binary(&PROJECT_METADATA.may_load(deps.storage, project_id.u128().into())?),
+
+        QueryMsg::GetProjectCount{ } =>
+            to_binary(&PROJECT_COUNT.may_load(deps.storage)?.unwrap_or_default()),
+
+        QueryMsg::IsCancelled{ project_id } =>
+            to_binary(&CANCELLED_PROJECTS.has(deps.storage, project_id.u128().into())),
+
+        QueryMsg::GetProjectTokens{ project_id } =>
+            to_binary(&PROJECT_TOKENS.may_load(deps.storage, project_id.u128().into())?.unwrap_or_default()),
+
         QueryMsg::GetOwner{ } => {
-            let owner = OWNER.load(deps.storage).unwrap();
+            let owner = OWNER.load(deps.storage)?;
             to_binary(&owner)
         }
+
+        QueryMsg::GetVestingAddr{ project_id } =>
+            to_binary(&get_vesting_addr(deps.storage, project_id)?),
+
+        QueryMsg::GetVestingSyncStatus{ project_id } =>
+            to_binary(&query_vestingsyncstatus(deps, project_id)?),
+
+        QueryMsg::GetVestingUserSync{ project_id } =>
+            to_binary(&VESTING_USER_SYNC.may_load(deps.storage, project_id.u128().into())?),
+
+        QueryMsg::GetTreasury{ project_id } =>
+            to_binary(&query_treasury(deps, project_id)?),
+
+        QueryMsg::GetContribution{ project_id, wallet } =>
+            to_binary(&query_contribution(deps, project_id, wallet)?),
+
+        QueryMsg::GetVotingConfig{ } =>
+            to_binary(&VOTING_CONFIG.may_load(deps.storage)?.unwrap_or_else(default_voting_config)),
+
+        QueryMsg::GetProposal{ project_id, proposal_id } =>
+            to_binary(&PROPOSALS.load(deps.storage, (project_id.u128().into(), proposal_id.into()))?),
+
+        QueryMsg::GetAllProposals{ project_id } =>
+            to_binary(&query_allproposals(deps, project_id)?),
+
+        QueryMsg::GetWindDown{ project_id } =>
+            to_binary(&WIND_DOWNS.may_load(deps.storage, project_id.u128().into())?),
+
+        QueryMsg::GetMoneyMarket{ } =>
+            to_binary(&MONEY_MARKET.may_load(deps.storage)?),
+
+        QueryMsg::GetMarketPosition{ project_id } =>
+            to_binary(&query_marketposition(deps, project_id)?),
+
+        QueryMsg::GetFeeConfig{ } =>
+            to_binary(&FEE_CONFIG.may_load(deps.storage)?),
+
+        QueryMsg::GetFees{ project_id } =>
+            to_binary(&project_fees(deps.storage, project_id)?),
+
+        QueryMsg::GetStageSale{ project_id, stage } =>
+            to_binary(&STAGE_SALES.may_load(deps.storage,
+                (project_id.u128().into(), stage.to_lowercase().as_str()))?),
+
+        QueryMsg::SimulateContribution{ project_id, stage, amount } =>
+            to_binary(&query_simulatecontribution(deps, _env, project_id, stage, amount)?),
+
+        QueryMsg::GetOverflowStatus{ project_id, stage } =>
+            to_binary(&OVERFLOW_STATUS.may_load(deps.storage,
+                (project_id.u128().into(), stage.to_lowercase().as_str()))?.unwrap_or_default()),
+
+        QueryMsg::GetOverflowDeposit{ project_id, stage, wallet } =>
+            to_binary(&query_overflowdeposit(deps, project_id, stage, wallet)?),
+
+        QueryMsg::GetCurrentPrice{ project_id, stage } =>
+            to_binary(&query_currentprice(deps, _env, project_id, stage)?),
+
+        QueryMsg::GetAuctionState{ project_id, stage } =>
+            to_binary(&AUCTION_STATES.may_load(deps.storage,
+                (project_id.u128().into(), stage.to_lowercase().as_str()))?.unwrap_or_else(new_auction_state)),
+
+        QueryMsg::GetBids{ project_id, stage, start_after, limit } =>
+            to_binary(&query_bids(deps, project_id, stage, start_after, limit)?),
+
+        QueryMsg::GetLottery{ project_id, stage } =>
+            to_binary(&LOTTERIES.may_load(deps.storage,
+                (project_id.u128().into(), stage.to_lowercase().as_str()))?.unwrap_or_default()),
+
+        QueryMsg::GetStakingContract{ } =>
+            to_binary(&STAKING_CONTRACT.may_load(deps.storage)?),
+
+        QueryMsg::GetProjectTiers{ project_id } =>
+            to_binary(&PROJECT_TIERS.may_load(deps.storage, project_id.u128().into())?),
+
+        QueryMsg::GetTierAllocation{ project_id, wallet } => {
+            let wallet = deps.api.addr_validate(&wallet)?;
+            to_binary(&tier_allocation(deps, _env.block.time.seconds(), project_id, &wallet)?)
+        }
+
+        QueryMsg::GetRegistration{ project_id } => {
+            let (total_weight, registrants) = REGISTERED_WEIGHT
+                .may_load(deps.storage, project_id.u128().into())?.unwrap_or_default();
+            to_binary(&RegistrationResponse{
+                config: REGISTRATIONS.may_load(deps.storage, project_id.u128().into())?,
+                total_weight,
+                registrants,
+            })
+        }
+
+        QueryMsg::GetKycConfig{ } =>
+            to_binary(&KYC_CONFIG.may_load(deps.storage)?),
+
+        QueryMsg::IsKycRevoked{ wallet } =>
+            to_binary(&KYC_REVOKED.has(deps.storage, &deps.api.addr_validate(&wallet)?)),
+
+        QueryMsg::GetCompliance{ } =>
+            to_binary(&COMPLIANCE.may_load(deps.storage)?),
+
+        QueryMsg::IsDenied{ project_id, wallet } =>
+            to_binary(&is_denied(deps.storage, project_id, &deps.api.addr_validate(&wallet)?)),
+
+        QueryMsg::GetDenyList{ project_id, start_after, limit } =>
+            to_binary(&query_denylist(deps, project_id, start_after, limit)?),
+
+        QueryMsg::GetReferralRates{ project_id } =>
+            to_binary(&REFERRAL_RATES.may_load(deps.storage, project_id.u128().into())?),
+
+        QueryMsg::GetReferralRewards{ project_id, referrer } => {
+            let referrer = deps.api.addr_validate(&referrer)?;
+            to_binary(&REFERRAL_REWARDS.may_load(deps.storage, (project_id.u128().into(), &referrer))?
+                .unwrap_or_default())
+        }
+
+        QueryMsg::GetTopReferrers{ project_id, limit } =>
+            to_binary(&query_topreferrers(deps, project_id, limit)?),
+
+        QueryMsg::GetBonusRules{ project_id, stage } =>
+            to_binary(&BONUS_RULES.may_load(deps.storage,
+                (project_id.u128().into(), stage.to_lowercase().as_str()))?),
+
+        QueryMsg::GetBonus{ project_id, wallet } => {
+            let wallet = deps.api.addr_validate(&wallet)?;
+            let x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+            to_binary(&bonus_of(deps.storage, &x, &wallet, _env.block.time.seconds())?)
+        }
+
+        QueryMsg::GetLiquidityConfig{ project_id } =>
+            to_binary(&LIQUIDITY_CONFIGS.may_load(deps.storage, project_id.u128().into())?),
+
+        QueryMsg::GetLiquidityPosition{ project_id } =>
+            to_binary(&LIQUIDITY_POSITIONS.may_load(deps.storage, project_id.u128().into())?),
+
+        QueryMsg::GetLotteryEntry{ project_id, stage, wallet } => {
+            let wallet = deps.api.addr_validate(&wallet)?;
+            to_binary(&LOTTERY_ENTRIES.may_load(deps.storage,
+                (project_id.u128().into(), stage.to_lowercase().as_str(), &wallet))?.unwrap_or_default())
+        }
             
     }
 }
 
 fn query_getallprojectinfo(deps: Deps) -> StdResult<Vec<ProjectInfo>>
 {
-    let all: StdResult<Vec<_>> = PROJECT_INFOS.range(deps.storage, None, None, 
-        cosmwasm_std::Order::Ascending).collect();
-    let all = all.unwrap();
+    let all: Vec<_> = PROJECT_INFOS.range(deps.storage, None, None, 
+        cosmwasm_std::Order::Ascending).collect::<StdResult<_>>()?;
 
     let mut all_project:Vec<ProjectInfo> = Vec::new();
     for x in all{
-        all_project.push(x.1);
+        if !ARCHIVED_PROJECTS.has(deps.storage, x.1.project_id.u128().into()) {
+            all_project.push(x.1);
+        }
     }
     Ok(all_project)
 }
 
+fn query_archivedprojects(deps: Deps) -> StdResult<Vec<Uint128>>
+{
+    let mut ids: Vec<Uint128> = Vec::new();
+    for item in PROJECT_INFOS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, x) = item?;
+        if ARCHIVED_PROJECTS.has(deps.storage, x.project_id.u128().into()) {
+            ids.push(x.project_id);
+        }
+    }
+    Ok(ids)
+}
+
 fn query_getprojectinfo(deps:Deps, project_id: Uint128) -> StdResult<ProjectInfo>
 {
     let x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
@@ -82,3 +261,116 @@ fn query_getconfig(deps:Deps, project_id: Uint128) -> StdResult<Config> {
     let x = PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
     Ok(x.config)
 }
+fn query_vestingsyncstatus(deps:Deps, project_id: Uint128) -> StdResult<VestingSyncStatus> {
+    PROJECT_INFOS.load(deps.storage, project_id.u128().into())?;
+    let status = VESTING_SYNC.may_load(deps.storage, project_id.u128().into())?;
+    Ok(status.unwrap_or_default())
+}
+fn query_treasury(deps:Deps, project_id: Uint128) -> StdResult<TreasuryResponse> {
+    let treasury = TREASURIES.load(deps.storage, project_id.u128().into())?;
+    Ok(TreasuryResponse{
+        locked: treasury.total_raised.checked_sub(treasury.released)?,
+        treasury,
+    })
+}
+fn query_contribution(deps:Deps, project_id: Uint128, wallet: String) -> StdResult<Uint128> {
+    let wallet = deps.api.addr_validate(&wallet)?;
+    let amount = CONTRIBUTIONS.may_load(deps.storage, (project_id.u128().into(), &wallet))?;
+    Ok(amount.unwrap_or_default())
+}
+fn query_allproposals(deps:Deps, project_id: Uint128) -> StdResult<Vec<Proposal>> {
+    PROPOSALS.prefix(project_id.u128().into())
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(_, proposal)| proposal))
+        .collect()
+}
+fn query_marketposition(deps:Deps, project_id: Uint128) -> StdResult<MarketPositionResponse> {
+    let position = MARKET_POSITIONS.may_load(deps.storage, project_id.u128().into())?
+        .unwrap_or_default();
+    let value = match MONEY_MARKET.may_load(deps.storage)? {
+        Some(market) if !position.shares.is_zero() => {
+            let rate: ExchangeRateResponse = deps.querier.query_wasm_smart(
+                market.market_addr,
+                &MarketQueryMsg::ExchangeRate{}
+            )?;
+            position.shares * rate.exchange_rate
+        }
+        _ => Uint128::zero(),
+    };
+    Ok(MarketPositionResponse{ position, value })
+}
+fn query_simulatecontribution(deps:Deps, env: Env, project_id: Uint128, stage: String, amount: Uint128)
+    -> StdResult<SimulateContributionResponse>
+{
+    let stage = stage.to_lowercase();
+    let sale = STAGE_SALES.load(deps.storage, (project_id.u128().into(), stage.as_str()))?;
+    //a dutch auction sells at its price of the current block
+    let price = dutch_auction_price(&sale, env.block.time.seconds());
+    let token_amount = tokens_for_payment(&StageSale{ price, ..sale }, amount)?;
+    Ok(SimulateContributionResponse{
+        token_amount,
+        bonus_amount: stage_bonus(deps.storage, project_id, &stage, env.block.time.seconds(),
+            amount, token_amount)?,
+    })
+}
+fn query_overflowdeposit(deps:Deps, project_id: Uint128, stage: String, wallet: String) -> StdResult<Uint128> {
+    let wallet = deps.api.addr_validate(&wallet)?;
+    let deposit = OVERFLOW_DEPOSITS.may_load(deps.storage,
+        (project_id.u128().into(), stage.to_lowercase().as_str(), &wallet))?;
+    Ok(deposit.unwrap_or_default())
+}
+fn query_currentprice(deps:Deps, env: Env, project_id: Uint128, stage: String) -> StdResult<CurrentPriceResponse> {
+    let stage = stage.to_lowercase();
+    let sale = STAGE_SALES.load(deps.storage, (project_id.u128().into(), stage.as_str()))?;
+    let tokens_sold = TOKENS_SOLD.may_load(deps.storage, (project_id.u128().into(), stage.as_str()))?;
+    Ok(CurrentPriceResponse{
+        price: dutch_auction_price(&sale, env.block.time.seconds()),
+        tokens_sold: tokens_sold.unwrap_or_default(),
+    })
+}
+//bids from the highest max price down
+fn query_bids(deps:Deps, project_id: Uint128, stage: String, start_after: Option<Binary>,
+    limit: Option<u32>) -> StdResult<BidsResponse>
+{
+    let limit = limit.unwrap_or(30).min(100) as usize;
+    let max = start_after.map(|key| Bound::exclusive(key.to_vec()));
+    let bids = BIDS.prefix((project_id.u128().into(), stage.to_lowercase().as_str()))
+        .range(deps.storage, None, max, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(key, bid)| (Binary::from(key), bid)))
+        .collect::<StdResult<_>>()?;
+    Ok(BidsResponse{ bids })
+}
+
+//platform-wide list without a project_id, sorted by address
+fn query_denylist(deps:Deps, project_id: Option<Uint128>, start_after: Option<String>,
+    limit: Option<u32>) -> StdResult<DenyListResponse>
+{
+    let limit = limit.unwrap_or(30).min(100) as usize;
+    let min = start_after.map(|wallet| Bound::exclusive(wallet.as_bytes()));
+    let keys: Vec<Vec<u8>> = match project_id {
+        None => DENY_LIST.keys(deps.storage, min, None, Order::Ascending).take(limit).collect(),
+        Some(project_id) => PROJECT_DENY_LIST.prefix(project_id.u128().into())
+            .keys(deps.storage, min, None, Order::Ascending).take(limit).collect(),
+    };
+    let wallets = keys.into_iter()
+        .map(|key| String::from_utf8(key).map(Addr::unchecked)
+            .map_err(|_| StdError::invalid_utf8("deny list key")))
+        .collect::<StdResult<_>>()?;
+    Ok(DenyListResponse{ wallets })
+}
+
+fn query_topreferrers(deps:Deps, project_id: Uint128, limit: Option<u32>) -> StdResult<TopReferrersResponse>
+{
+    let limit = limit.unwrap_or(10).min(100) as usize;
+    let referrers = REFERRER_RANKS.sub_prefix(project_id.u128().into())
+        .range(deps.storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            let (_, referrer) = item?;
+            let rewards = REFERRAL_REWARDS.load(deps.storage, (project_id.u128().into(), &referrer))?;
+            Ok((referrer, rewards))
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(TopReferrersResponse{ referrers })
+}
@@ -0,0 +1,20 @@
+use cosmwasm_std::Uint128;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+//Query interface of the platform token staking contract.
+//`height` asks for the stake at a past block, None for the current stake.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    StakedAt {
+        address: String,
+        height: Option<u64>
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakedResponse {
+    pub stake: Uint128,
+}
@@ -1,7 +1,97 @@
-use cosmwasm_std::{Addr};
-use cw_storage_plus::{Item, Map, U128Key};
-use crate::msg::{ProjectInfo};
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map, U128Key, U64Key};
+use crate::msg::{ProjectInfo, VestingSyncStatus, VestingUserSync, Treasury, VotingConfig,
+    Proposal, WindDown, MoneyMarketConfig, MarketPosition, YieldCheckpoint, FeeConfig, FeeRates, PaidFees,
+    StageSale, OverflowStatus, Bid, AuctionState, LotteryState, LotteryEntry, ProjectTiers,
+    RegistrationConfig, Registrant, KycConfig, ReferralRates, ReferralRewards,
+    BonusRules, LiquidityConfig, LiquidityPosition, ProjectMetadata};
 
 pub const OWNER: Item<Addr> = Item::new("owner");
 pub const VESTING_ADDR: Item<Addr> = Item::new("vesting_address");
+pub const VESTING_CODE_ID: Item<u64> = Item::new("vesting_code_id");
 pub const PROJECT_INFOS:Map<U128Key, ProjectInfo> = Map::new("project_infos");
+//last id given to an AddProject without an id, explicit ids leave it alone
+pub const PROJECT_COUNT: Item<Uint128> = Item::new("project_count");
+pub const PROJECT_METADATA: Map<U128Key, ProjectMetadata> = Map::new("project_metadata");
+
+//project of the vesting message in flight, consumed by reply
+pub const PENDING_VESTING_PROJECT: Item<Uint128> = Item::new("pending_vesting_project");
+pub const PROJECT_VESTING_ADDRS: Map<U128Key, Addr> = Map::new("project_vesting_addrs");
+pub const VESTING_SYNC: Map<U128Key, VestingSyncStatus> = Map::new("vesting_sync");
+pub const VESTING_USER_SYNC: Map<U128Key, VestingUserSync> = Map::new("vesting_user_sync");
+
+pub const TREASURIES: Map<U128Key, Treasury> = Map::new("treasuries");
+pub const CONTRIBUTIONS: Map<(U128Key, &Addr), Uint128> = Map::new("contributions");
+
+pub const VOTING_CONFIG: Item<VotingConfig> = Item::new("voting_config");
+pub const PROPOSAL_COUNT: Map<U128Key, u64> = Map::new("proposal_count");
+pub const PROPOSALS: Map<(U128Key, U64Key), Proposal> = Map::new("proposals");
+pub const VOTES: Map<(U128Key, U64Key, &Addr), bool> = Map::new("votes");
+pub const WIND_DOWNS: Map<U128Key, WindDown> = Map::new("wind_downs");
+//allocation of each investor when the wind down passed
+pub const WIND_DOWN_WEIGHTS: Map<(U128Key, &Addr), Uint128> = Map::new("wind_down_weights");
+pub const REFUND_CLAIMED: Map<(U128Key, &Addr), bool> = Map::new("refund_claimed");
+
+pub const MONEY_MARKET: Item<MoneyMarketConfig> = Item::new("money_market");
+pub const MARKET_POSITIONS: Map<U128Key, MarketPosition> = Map::new("market_positions");
+pub const YIELD_CHECKPOINTS: Map<(U128Key, &Addr), YieldCheckpoint> = Map::new("yield_checkpoints");
+
+pub const FEE_CONFIG: Item<FeeConfig> = Item::new("fee_config");
+pub const PROJECT_FEES: Map<U128Key, FeeRates> = Map::new("project_fees");
+pub const FEES_PAID: Map<U128Key, PaidFees> = Map::new("fees_paid");
+pub const SALE_FINALIZED: Map<U128Key, bool> = Map::new("sale_finalized");
+
+pub const STAGE_SALES: Map<(U128Key, &str), StageSale> = Map::new("stage_sales");
+pub const OVERFLOW_STATUS: Map<(U128Key, &str), OverflowStatus> = Map::new("overflow_status");
+pub const OVERFLOW_DEPOSITS: Map<(U128Key, &str, &Addr), Uint128> = Map::new("overflow_deposits");
+pub const TOKENS_SOLD: Map<(U128Key, &str), Uint128> = Map::new("tokens_sold");
+
+//bids are keyed by (max price, id) so they can be walked from the highest price
+pub const BIDS: Map<(U128Key, &str, (U128Key, U64Key)), Bid> = Map::new("bids");
+//payment escrowed by each bidder of a stage, counted against its tier cap
+pub const BIDDER_TOTALS: Map<(U128Key, &str, &Addr), Uint128> = Map::new("bidder_totals");
+pub const AUCTION_STATES: Map<(U128Key, &str), AuctionState> = Map::new("auction_states");
+
+pub const LOTTERIES: Map<(U128Key, &str), LotteryState> = Map::new("lotteries");
+pub const LOTTERY_ENTRIES: Map<(U128Key, &str, &Addr), LotteryEntry> = Map::new("lottery_entries");
+//owner of each registration, keyed by its first ticket number
+pub const TICKET_RANGES: Map<(U128Key, &str, U64Key), Addr> = Map::new("ticket_ranges");
+//positions moved by the draw shuffle, unset positions hold their own ticket
+pub const LOTTERY_SWAPS: Map<(U128Key, &str, U64Key), u64> = Map::new("lottery_swaps");
+
+pub const STAKING_CONTRACT: Item<Addr> = Item::new("staking_contract");
+pub const PROJECT_TIERS: Map<U128Key, ProjectTiers> = Map::new("project_tiers");
+
+pub const REGISTRATIONS: Map<U128Key, RegistrationConfig> = Map::new("registrations");
+pub const REGISTRANTS: Map<(U128Key, &Addr), Registrant> = Map::new("registrants");
+//(total weight, registrants) of each project registration
+pub const REGISTERED_WEIGHT: Map<U128Key, (Uint128, u64)> = Map::new("registered_weight");
+
+pub const KYC_CONFIG: Item<KycConfig> = Item::new("kyc_config");
+pub const KYC_REVOKED: Map<&Addr, bool> = Map::new("kyc_revoked");
+
+pub const COMPLIANCE: Item<Addr> = Item::new("compliance");
+pub const DENY_LIST: Map<&Addr, bool> = Map::new("deny_list");
+pub const PROJECT_DENY_LIST: Map<(U128Key, &Addr), bool> = Map::new("project_deny_list");
+
+pub const REFERRAL_RATES: Map<U128Key, ReferralRates> = Map::new("referral_rates");
+pub const REFERRAL_REWARDS: Map<(U128Key, &Addr), ReferralRewards> = Map::new("referral_rewards");
+//referrers keyed by referred payment, for the top referrers query
+pub const REFERRER_RANKS: Map<(U128Key, U128Key, &Addr), Addr> = Map::new("referrer_ranks");
+//referrer of an overflow deposit, credited when the allocation is claimed
+pub const OVERFLOW_REFERRERS: Map<(U128Key, &str, &Addr), Addr> = Map::new("overflow_referrers");
+
+pub const BONUS_RULES: Map<(U128Key, &str), BonusRules> = Map::new("bonus_rules");
+
+pub const LIQUIDITY_CONFIGS: Map<U128Key, LiquidityConfig> = Map::new("liquidity_configs");
+pub const LIQUIDITY_POSITIONS: Map<U128Key, LiquidityPosition> = Map::new("liquidity_positions");
+//project of the pair creation or liquidity deposit in flight, consumed by reply
+pub const PENDING_LIQUIDITY_PROJECT: Item<Uint128> = Item::new("pending_liquidity_project");
+//LP balance of the contract before the liquidity deposit in flight
+pub const PENDING_LP_BALANCE: Item<Uint128> = Item::new("pending_lp_balance");
+
+pub const CANCELLED_PROJECTS: Map<U128Key, bool> = Map::new("cancelled_projects");
+//sale tokens deposited for the project and not paid out yet, returned on cancel
+pub const PROJECT_TOKENS: Map<U128Key, Uint128> = Map::new("project_tokens");
+//finished projects left out of the project listing
+pub const ARCHIVED_PROJECTS: Map<U128Key, bool> = Map::new("archived_projects");
@@ -1,24 +1,39 @@
-use super::*;
-use cosmwasm_std::{from_binary, Addr, CosmosMsg, WasmMsg,
-    BankQuery, BalanceResponse, AllBalanceResponse, Coin, Uint128};
-use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR, mock_dependencies};
+use cosmwasm_std::{from_binary, to_binary, Addr, CosmosMsg, WasmMsg, Uint128, DepsMut, Coin,
+    BankMsg, Decimal, Reply, ContractResult, SubMsgExecutionResponse, Event};
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 
-use crate::contract::{execute, instantiate};
+use crate::contract::{execute, instantiate, reply, INSTANTIATE_VESTING_REPLY_ID,
+    VESTING_ADD_PROJECT_REPLY_ID, LIQUIDITY_CREATE_PAIR_REPLY_ID,
+    LIQUIDITY_PROVIDE_REPLY_ID};
 use crate::query::{query};
 
-use crate::msg::{QueryMsg, ExecuteMsg, InstantiateMsg, VestingParameter, Config, UserInfo, ProjectInfo};
+use crate::msg::{QueryMsg, ExecuteMsg, InstantiateMsg, ProjectInfo, SyncState, VestingSyncStatus,
+    VestingUserSync, TreasuryParams, MilestoneParam, TreasuryResponse, Proposal, ProposalStatus,
+    MarketPositionResponse, FeeRates, FeesResponse, StageSale, SimulateContributionResponse,
+    SaleType, OverflowStatus, CurrentPriceResponse, AuctionState, AuctionPhase, BidsResponse,
+    WhitelistEntry, LotteryState, LotteryEntry, ProjectTiers, Tier, TierAllocation,
+    TierAllocationResponse, RegistrationConfig, RegistrationResponse, KycConfig, KycScheme, KycProof,
+    KycPayload, DenyListResponse, ReferralRates, ReferralRewards, TopReferrersResponse,
+    BonusRules, TimeBonus, AmountBonus, BonusResponse, LiquidityConfig, LiquidityPosition,
+    UserInfo, ImportMode, ProjectMetadata, SocialLink, Config, Cw20HookMsg};
+use crate::ContractError;
 
-// use crate::mock_querier::mock_dependencies;
-use cw20::Cw20ExecuteMsg;
+use crate::mock_querier::mock_dependencies;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use crate::money_market::ExecuteMsg as MarketExecuteMsg;
+use crate::vesting::ExecuteMsg as VestingExecuteMsg;
+use crate::dex::{Asset, AssetInfo, PairExecuteMsg};
 // use terraswap::asset::{Asset, AssetInfo};
 // use terraswap::pair::ExecuteMsg as TerraswapExecuteMsg;
 
 #[test]
 fn workflow(){
     let mut deps = mock_dependencies(&[]);
-    
+
     let msg = InstantiateMsg{
         admin: Some(String::from("admin")),
+        vesting_code_id: None,
     };
 //instantiate
     let info = mock_info("admin", &[]);
@@ -26,29 +41,34 @@ fn workflow(){
 
     let msg = ExecuteMsg::SetConfig{
         admin: String::from("admin"),
-        vesting_addr: String::from("vesting_contract")
+        vesting_addr: Some(String::from("vesting_contract")),
+        vesting_code_id: None,
     };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("attacker", &[]), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { }));
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     println!("Set Config{:?}", res);
 
 
     let msg = ExecuteMsg::AddProject{
-        project_id: Uint128::from(1u64),
+        project_id: Some(Uint128::from(1u64)),
         admin: String::from("admin"),
         token_addr: String::from("WeFund"),
         start_time: Some(Uint128::from(1645771274u128)),
-        vesting_params: Vec::new()
+        vesting_params: Vec::new(),
+        treasury: None,
+        metadata: None
     };
     // let msg = ExecuteMsg::AddSeedUser{
-    //     project_id: 
+    //     project_id:
     //     wallet: Addr::unchecked("seed1".to_string()),
     //     amount: Uint128::new(100)
     // };
 
-    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     println!("Add project{:?}", res);
 
-    
+
 
 //-Remove Project-------------------------
 
@@ -64,3 +84,2263 @@ fn workflow(){
     println!("Project Info {:?}", res );
 }
 
+#[test]
+fn vesting_per_project(){
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg{
+        admin: Some(String::from("admin")),
+        vesting_code_id: Some(7),
+    };
+    let info = mock_info("admin", &[]);
+    instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddProject{
+        project_id: Some(Uint128::from(1u64)),
+        admin: String::from("admin"),
+        token_addr: String::from("WeFund"),
+        start_time: None,
+        vesting_params: Vec::new(),
+        treasury: None,
+        metadata: None
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, INSTANTIATE_VESTING_REPLY_ID);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate{ code_id, .. }) => assert_eq!(*code_id, 7),
+        _ => panic!("expected vesting instantiate"),
+    }
+
+//-Reply with the new vesting contract-----------------
+    let event = Event::new("instantiate")
+        .add_attribute("code_id", "7")
+        .add_attribute("_contract_address", "vesting1");
+    let msg = Reply{
+        id: INSTANTIATE_VESTING_REPLY_ID,
+        result: ContractResult::Ok(SubMsgExecutionResponse{
+            events: vec![event],
+            data: None
+        })
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute{ contract_addr, .. }) => assert_eq!(contract_addr, "vesting1"),
+        _ => panic!("expected vesting add project"),
+    }
+
+    let msg = QueryMsg::GetVestingAddr{project_id: Uint128::from(1u64)};
+    let res: Option<Addr> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Some(Addr::unchecked("vesting1")));
+}
+
+#[test]
+fn vesting_sync_retry(){
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg{
+        admin: Some(String::from("admin")),
+        vesting_code_id: None,
+    };
+    let info = mock_info("admin", &[]);
+    instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::SetConfig{
+        admin: String::from("admin"),
+        vesting_addr: Some(String::from("vesting_contract")),
+        vesting_code_id: None,
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddProject{
+        project_id: Some(Uint128::from(1u64)),
+        admin: String::from("admin"),
+        token_addr: String::from("WeFund"),
+        start_time: None,
+        vesting_params: Vec::new(),
+        treasury: None,
+        metadata: None
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages[0].id, VESTING_ADD_PROJECT_REPLY_ID);
+
+//-Vesting contract rejects the project-----------------
+    let msg = Reply{
+        id: VESTING_ADD_PROJECT_REPLY_ID,
+        result: ContractResult::Err(String::from("Unauthorized"))
+    };
+    reply(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let msg = QueryMsg::GetVestingSyncStatus{project_id: Uint128::from(1u64)};
+    let res: VestingSyncStatus = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.add_project, SyncState::Failed);
+    assert_eq!(res.last_error, Some(String::from("Unauthorized")));
+
+//-Retry-----------------
+    let msg = ExecuteMsg::RetryVestingSync{project_id: Uint128::from(1u64)};
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages[0].id, VESTING_ADD_PROJECT_REPLY_ID);
+
+    let msg = Reply{
+        id: VESTING_ADD_PROJECT_REPLY_ID,
+        result: ContractResult::Ok(SubMsgExecutionResponse{
+            events: Vec::new(),
+            data: None
+        })
+    };
+    reply(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let msg = QueryMsg::GetVestingSyncStatus{project_id: Uint128::from(1u64)};
+    let res: VestingSyncStatus = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.add_project, SyncState::Success);
+
+    let msg = ExecuteMsg::RetryVestingSync{project_id: Uint128::from(1u64)};
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert!(res.is_err());
+}
+
+#[test]
+fn sync_vesting_users_in_pages(){
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg{
+        admin: Some(String::from("admin")),
+        vesting_code_id: None,
+    };
+    let info = mock_info("admin", &[]);
+    instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::SetConfig{
+        admin: String::from("admin"),
+        vesting_addr: Some(String::from("vesting_contract")),
+        vesting_code_id: None,
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddProject{
+        project_id: Some(Uint128::from(1u64)),
+        admin: String::from("admin"),
+        token_addr: String::from("WeFund"),
+        start_time: None,
+        vesting_params: Vec::new(),
+        treasury: None,
+        metadata: None
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    for wallet in ["seed1", "seed2", "seed3"] {
+        let msg = ExecuteMsg::AddSeedUser{
+            project_id: Uint128::from(1u64),
+            wallet: Addr::unchecked(wallet),
+            amount: Uint128::new(100)
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    }
+    let msg = ExecuteMsg::AddPresaleUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("presale1"),
+        amount: Uint128::new(100)
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::SyncVestingUsers{project_id: Uint128::from(1u64), limit: None};
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), msg).is_err());
+
+    let msg = ExecuteMsg::StartVesting{project_id: Uint128::from(1u64)};
+    let err = execute(deps.as_mut(), mock_env(), mock_info("seed1", &[]), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { }));
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::VestingStarted { }));
+
+    //the block time becomes the start time, saved and sent to the vesting contract
+    let start_time = Uint128::from(mock_env().block.time.seconds());
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let project: ProjectInfo = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(project.config.start_time, start_time);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute{ msg, .. }) => match from_binary(msg).unwrap() {
+            VestingExecuteMsg::SetProjectInfo{ project_info, .. } =>
+                assert_eq!(project_info.config.start_time, start_time),
+            _ => panic!("expected vesting set project info"),
+        },
+        _ => panic!("expected vesting set project info"),
+    }
+    assert!(res.events[0].attributes.iter()
+        .any(|a| a.key == "start_time" && a.value == start_time.to_string()));
+
+    let msg = ExecuteMsg::AddSeedUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("seed4"),
+        amount: Uint128::new(100)
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::VestingStarted { }));
+    let msg = ExecuteMsg::SetSeedUsers{project_id: Uint128::from(1u64), user_infos: Vec::new()};
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::VestingStarted { }));
+
+    //a failed sync can still be retried
+    let msg = ExecuteMsg::RetryVestingSync{project_id: Uint128::from(1u64)};
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages[0].id, VESTING_ADD_PROJECT_REPLY_ID);
+
+//-Sync in pages of two investors-----------------
+    let msg = ExecuteMsg::SyncVestingUsers{project_id: Uint128::from(1u64), limit: Some(2)};
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+
+    //removing a synced investor would shift the pages
+    let msg = ExecuteMsg::RemoveUser{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        wallet: String::from("seed1")
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::VestingStarted { }));
+
+    let msg = ExecuteMsg::SyncVestingUsers{project_id: Uint128::from(1u64), limit: Some(2)};
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 2);
+
+    let msg = QueryMsg::GetVestingUserSync{project_id: Uint128::from(1u64)};
+    let res: Option<VestingUserSync> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Some(VestingUserSync{ stage: String::from("ido"), index: 0, finished: false }));
+
+    let msg = ExecuteMsg::SyncVestingUsers{project_id: Uint128::from(1u64), limit: Some(2)};
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = QueryMsg::GetVestingUserSync{project_id: Uint128::from(1u64)};
+    let res: Option<VestingUserSync> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert!(res.unwrap().finished);
+
+    let msg = ExecuteMsg::SyncVestingUsers{project_id: Uint128::from(1u64), limit: Some(2)};
+    assert!(execute(deps.as_mut(), mock_env(), info, msg).is_err());
+}
+
+//instantiate with "admin" as owner and add project 1 without a vesting contract
+fn init_project(mut deps: DepsMut, treasury: Option<TreasuryParams>){
+    let msg = InstantiateMsg{
+        admin: Some(String::from("admin")),
+        vesting_code_id: None,
+    };
+    let info = mock_info("admin", &[]);
+    instantiate(deps.branch(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddProject{
+        project_id: Some(Uint128::from(1u64)),
+        admin: String::from("project_admin"),
+        token_addr: String::from("WeFund"),
+        start_time: None,
+        vesting_params: Vec::new(),
+        treasury,
+        metadata: None
+    };
+    execute(deps, mock_env(), info, msg).unwrap();
+}
+
+//sale tokens of project 1 sent through the cw20 hook
+fn deposit_tokens(deps: DepsMut, amount: u128){
+    let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg{
+        sender: String::from("project_admin"),
+        amount: Uint128::new(amount),
+        msg: to_binary(&Cw20HookMsg::DepositTokens{project_id: Uint128::from(1u64)}).unwrap()
+    });
+    execute(deps, mock_env(), mock_info("WeFund", &[]), msg).unwrap();
+}
+
+#[test]
+fn treasury_milestones(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: vec![
+            MilestoneParam{ description: String::from("MVP"), amount: Uint128::new(300) },
+            MilestoneParam{ description: String::from("Launch"), amount: Uint128::new(700) },
+        ]
+    }));
+
+//-Contribute-----------------
+    let info = mock_info("investor", &[Coin::new(100, "uluna")]);
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    assert!(execute(deps.as_mut(), mock_env(), info, msg).is_err());
+
+    let info = mock_info("investor", &[Coin::new(500, "uusd")]);
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = QueryMsg::GetContribution{project_id: Uint128::from(1u64), wallet: String::from("investor")};
+    let res: Uint128 = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Uint128::new(500));
+
+//-Release needs approval-----------------
+    let info = mock_info("project_admin", &[]);
+    let msg = ExecuteMsg::ReleaseMilestone{project_id: Uint128::from(1u64), milestone_index: 0};
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).is_err());
+
+    let approve = ExecuteMsg::ApproveMilestone{project_id: Uint128::from(1u64), milestone_index: 0};
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), approve.clone()).is_err());
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), approve).unwrap();
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("project_wallet"),
+        amount: vec![Coin::new(300, "uusd")]
+    }));
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), msg).is_err());
+
+//-Not enough raised for the second milestone-----------------
+    let approve = ExecuteMsg::ApproveMilestone{project_id: Uint128::from(1u64), milestone_index: 1};
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), approve).unwrap();
+    let msg = ExecuteMsg::ReleaseMilestone{project_id: Uint128::from(1u64), milestone_index: 1};
+    assert!(execute(deps.as_mut(), mock_env(), info, msg).is_err());
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.treasury.released, Uint128::new(300));
+    assert_eq!(res.locked, Uint128::new(200));
+}
+
+#[test]
+fn wind_down_vote_and_refund(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    deposit_tokens(deps.as_mut(), 100_000);
+
+    let info = mock_info("admin", &[]);
+    for (wallet, amount) in [("seed1", 300u128), ("seed2", 100u128)] {
+        let msg = ExecuteMsg::AddSeedUser{
+            project_id: Uint128::from(1u64),
+            wallet: Addr::unchecked(wallet),
+            amount: Uint128::new(amount)
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    }
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("seed1", &[Coin::new(400, "uusd")]), msg).unwrap();
+
+    let msg = ExecuteMsg::SetMoneyMarket{
+        market_addr: String::from("market"),
+        platform_wallet: String::from("platform"),
+        platform_share: Decimal::percent(20),
+        project_share: Decimal::percent(30),
+        investor_share: Decimal::percent(50),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::DepositIdleFunds{project_id: Uint128::from(1u64), amount: Uint128::new(200)};
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+//-Voting config-----------------
+    let config = |quorum, threshold, voting_period| ExecuteMsg::SetVotingConfig{quorum, threshold, voting_period};
+    let err = execute(deps.as_mut(), mock_env(), mock_info("stranger", &[]),
+        config(Decimal::percent(30), Decimal::percent(50), 100)).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { }));
+    for msg in [
+        config(Decimal::percent(101), Decimal::percent(50), 100),
+        config(Decimal::percent(30), Decimal::percent(101), 100),
+        config(Decimal::percent(30), Decimal::percent(50), 0),
+        config(Decimal::percent(30), Decimal::percent(50), u64::MAX),
+    ] {
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVotingConfig { }));
+    }
+
+//-Propose and vote-----------------
+    let msg = ExecuteMsg::ProposeWindDown{project_id: Uint128::from(1u64), description: String::from("rug")};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("stranger", &[]), msg.clone()).is_err());
+    execute(deps.as_mut(), mock_env(), mock_info("seed1", &[]), msg.clone()).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("seed2", &[]), msg).is_err());
+
+    let vote = |wind_down| ExecuteMsg::Vote{project_id: Uint128::from(1u64), proposal_id: 1, wind_down};
+    execute(deps.as_mut(), mock_env(), mock_info("seed1", &[]), vote(true)).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("seed1", &[]), vote(true)).is_err());
+    //voting weights can't grow during the vote
+    let msg = ExecuteMsg::AddSeedUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("seed2"),
+        amount: Uint128::new(1000)
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::ProposalOpen { }));
+    execute(deps.as_mut(), mock_env(), mock_info("seed2", &[]), vote(false)).unwrap();
+
+    let msg = ExecuteMsg::EndProposal{project_id: Uint128::from(1u64), proposal_id: 1};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg.clone()).is_err());
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(7 * 24 * 60 * 60);
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+    //the deposited funds are redeemed into the refund pool
+    assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute{
+        contract_addr: String::from("market"),
+        msg: to_binary(&MarketExecuteMsg::Redeem{ shares: Uint128::new(200) }).unwrap(),
+        funds: Vec::new()
+    }));
+
+    let msg = QueryMsg::GetProposal{project_id: Uint128::from(1u64), proposal_id: 1};
+    let res: Proposal = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.status, ProposalStatus::Passed);
+
+//-Refunds-----------------
+    //allocations added after the vote don't change the refund weights
+    let msg = ExecuteMsg::AddSeedUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("seed2"),
+        amount: Uint128::new(400)
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::ClaimRefund{project_id: Uint128::from(1u64)};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("seed2", &[]), msg.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("seed2"),
+        amount: vec![Coin::new(100, "uusd")]
+    }));
+    assert_eq!(res.messages.len(), 2);
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("seed2", &[]), msg).is_err());
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.treasury.released, Uint128::new(100));
+
+    let msg = ExecuteMsg::FinalizeSale{project_id: Uint128::from(1u64)};
+    let err = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::ProjectWoundDown { }));
+
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("seed1", &[Coin::new(1, "uusd")]), msg).is_err());
+}
+
+#[test]
+fn money_market_yield(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: vec![
+            MilestoneParam{ description: String::from("MVP"), amount: Uint128::new(1000) },
+        ]
+    }));
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1000, "uusd")]), msg).unwrap();
+
+    let msg = ExecuteMsg::SetMoneyMarket{
+        market_addr: String::from("market"),
+        platform_wallet: String::from("platform"),
+        platform_share: Decimal::percent(20),
+        project_share: Decimal::percent(30),
+        investor_share: Decimal::percent(40),
+    };
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).is_err());
+    let msg = ExecuteMsg::SetMoneyMarket{
+        market_addr: String::from("market"),
+        platform_wallet: String::from("platform"),
+        platform_share: Decimal::percent(20),
+        project_share: Decimal::percent(30),
+        investor_share: Decimal::percent(50),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+//-Deposit at rate 1.25-----------------
+    deps.querier.with_exchange_rate(Decimal::from_ratio(5u128, 4u128));
+    let msg = ExecuteMsg::DepositIdleFunds{project_id: Uint128::from(1u64), amount: Uint128::new(1000)};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute{
+        contract_addr: String::from("market"),
+        msg: to_binary(&MarketExecuteMsg::Deposit{}).unwrap(),
+        funds: vec![Coin::new(1000, "uusd")]
+    }));
+
+    //deposited funds can't be released
+    let approve = ExecuteMsg::ApproveMilestone{project_id: Uint128::from(1u64), milestone_index: 0};
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), approve).unwrap();
+    let msg = ExecuteMsg::ReleaseMilestone{project_id: Uint128::from(1u64), milestone_index: 0};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).is_err());
+
+//-Redeem at rate 1.5 earns 200-----------------
+    deps.querier.with_exchange_rate(Decimal::from_ratio(3u128, 2u128));
+    let msg = QueryMsg::GetMarketPosition{project_id: Uint128::from(1u64)};
+    let res: MarketPositionResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.position.shares, Uint128::new(800));
+    assert_eq!(res.value, Uint128::new(1200));
+
+    let msg = ExecuteMsg::RedeemIdleFunds{project_id: Uint128::from(1u64), shares: None};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("platform"),
+        amount: vec![Coin::new(40, "uusd")]
+    }));
+    assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("project_wallet"),
+        amount: vec![Coin::new(60, "uusd")]
+    }));
+
+//-Investors claim their share-----------------
+    //a contribution after the yield was earned has no share of it
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("late", &[Coin::new(1000, "uusd")]), msg).unwrap();
+    let msg = ExecuteMsg::ClaimYield{project_id: Uint128::from(1u64), kyc: None};
+    let err = execute(deps.as_mut(), mock_env(), mock_info("late", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::NoYield { }));
+
+    let msg = ExecuteMsg::ClaimYield{project_id: Uint128::from(1u64), kyc: None};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("investor"),
+        amount: vec![Coin::new(100, "uusd")]
+    }));
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg).is_err());
+}
+
+#[test]
+fn cancel_after_market_loss(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1000, "uusd")]), msg).unwrap();
+    let msg = ExecuteMsg::SetMoneyMarket{
+        market_addr: String::from("market"),
+        platform_wallet: String::from("platform"),
+        platform_share: Decimal::percent(20),
+        project_share: Decimal::percent(30),
+        investor_share: Decimal::percent(50),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+//-Redeem at rate 0.9 loses 100-----------------
+    deps.querier.with_exchange_rate(Decimal::one());
+    let msg = ExecuteMsg::DepositIdleFunds{project_id: Uint128::from(1u64), amount: Uint128::new(1000)};
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    deps.querier.with_exchange_rate(Decimal::percent(90));
+    let msg = ExecuteMsg::RedeemIdleFunds{project_id: Uint128::from(1u64), shares: None};
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    //contributions can no longer be refunded in full
+    let msg = ExecuteMsg::CancelProject{project_id: Uint128::from(1u64)};
+    let err = execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::FundsLost { }));
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.locked, Uint128::new(900));
+}
+
+#[test]
+fn platform_fees(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    deposit_tokens(deps.as_mut(), 100_000);
+    let info = mock_info("admin", &[]);
+    let msg = ExecuteMsg::AddIDOUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("investor"),
+        amount: Uint128::new(2000)
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1000, "uusd")]), msg).unwrap();
+
+    let msg = ExecuteMsg::SetFeeConfig{
+        collector: String::from("collector"),
+        raise_fee: Decimal::percent(5),
+        token_fee: Decimal::percent(1),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+//-Project override-----------------
+    let msg = ExecuteMsg::SetProjectFees{
+        project_id: Uint128::from(1u64),
+        fees: Some(FeeRates{ raise_fee: Decimal::percent(2), token_fee: Decimal::percent(1) })
+    };
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg.clone()).is_err());
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = QueryMsg::GetFees{project_id: Uint128::from(1u64)};
+    let res: FeesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.accrued_funds, Uint128::new(20));
+    assert_eq!(res.accrued_tokens, Uint128::new(20));
+
+//-Finalize pays the collector-----------------
+    let msg = ExecuteMsg::FinalizeSale{project_id: Uint128::from(1u64)};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("collector"),
+        amount: vec![Coin::new(20, "uusd")]
+    }));
+    assert_eq!(res.messages.len(), 2);
+    assert!(execute(deps.as_mut(), mock_env(), info, msg).is_err());
+
+    let msg = QueryMsg::GetFees{project_id: Uint128::from(1u64)};
+    let res: FeesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.accrued_funds, Uint128::zero());
+    assert_eq!(res.paid.funds, Uint128::new(20));
+
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1, "uusd")]), msg).is_err());
+}
+
+#[test]
+fn fixed_price_sale(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("ido")), kyc: None, referrer: None};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1000, "uusd")]), msg).is_err());
+
+    //0.03 uusd-coin per token, 6 decimals payment and 8 decimals token
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("IDO"),
+        sale: StageSale{
+            price: Decimal::from_ratio(3u128, 100u128),
+            payment_decimals: 6,
+            token_decimals: 8,
+            sale_type: SaleType::FixedPrice
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let msg = QueryMsg::SimulateContribution{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        amount: Uint128::new(1_000_000)
+    };
+    let res: SimulateContributionResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.token_amount, Uint128::new(3_333_333_333));
+
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("ido")), kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1_000_000, "uusd")]), msg).unwrap();
+
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.ido_users[0].wallet_address, Addr::unchecked("investor"));
+    assert_eq!(res.ido_users[0].total_amount, Uint128::new(3_333_333_333));
+
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("private"),
+        sale: StageSale{ price: Decimal::one(), payment_decimals: 6, token_decimals: 6,
+            sale_type: SaleType::FixedPrice }
+    };
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).is_err());
+}
+
+#[test]
+fn overflow_sale(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let start = mock_env().block.time.seconds();
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::percent(50),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::Overflow{ cap: Uint128::new(1000), start_time: start, end_time: start + 100 }
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+//-Deposit 3000 against a cap of 1000-----------------
+    let deposit = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("ido")), kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(1000, "uusd")]), deposit.clone()).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(2000, "uusd")]), deposit.clone()).unwrap();
+
+    //the deposits are tied to the overflow sale
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::percent(50),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::FixedPrice
+        }
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::StageHasEscrow { }));
+
+    let claim = ExecuteMsg::ClaimAllocationAndRefund{project_id: Uint128::from(1u64), stage: String::from("ido"), kyc: None};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), claim.clone()).is_err());
+
+    let finalize = ExecuteMsg::FinalizeStage{project_id: Uint128::from(1u64), stage: String::from("ido")};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), finalize.clone()).is_err());
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("carol", &[Coin::new(10, "uusd")]), deposit).is_err());
+    execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), finalize).unwrap();
+
+    let msg = QueryMsg::GetOverflowStatus{project_id: Uint128::from(1u64), stage: String::from("ido")};
+    let res: OverflowStatus = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res, OverflowStatus{ total_deposits: Uint128::new(3000), finalized: true });
+
+//-Claims-----------------
+    let res = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), claim.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("bob"),
+        amount: vec![Coin::new(1334, "uusd")]
+    }));
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).unwrap();
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim).is_err());
+
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.ido_users[0].total_amount, Uint128::new(1332));
+    assert_eq!(res.ido_users[1].total_amount, Uint128::new(666));
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    assert_eq!(res.treasury.total_raised, Uint128::new(999));
+}
+
+#[test]
+fn dutch_auction(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let start = mock_env().block.time.seconds();
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::DutchAuction{
+                start_price: Decimal::percent(300),
+                start_time: start,
+                end_time: start + 100,
+                token_cap: Uint128::new(1000)
+            }
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+//-Price halfway through the window-----------------
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(50);
+    let msg = QueryMsg::GetCurrentPrice{project_id: Uint128::from(1u64), stage: String::from("ido")};
+    let res: CurrentPriceResponse = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.price, Decimal::percent(200));
+
+    let msg = QueryMsg::SimulateContribution{project_id: Uint128::from(1u64), stage: String::from("ido"), amount: Uint128::new(1000)};
+    let res: SimulateContributionResponse = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.token_amount, Uint128::new(500));
+
+    let bid = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("ido")), kyc: None, referrer: None};
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &[Coin::new(1000, "uusd")]), bid.clone()).unwrap();
+
+//-Later bids get a lower price-----------------
+    env.block.time = env.block.time.plus_seconds(25);
+    execute(deps.as_mut(), env.clone(), mock_info("bob", &[Coin::new(600, "uusd")]), bid.clone()).unwrap();
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("carol", &[Coin::new(1000, "uusd")]), bid.clone()).is_err());
+
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.ido_users[0].total_amount, Uint128::new(500));
+    assert_eq!(res.ido_users[1].total_amount, Uint128::new(400));
+
+    env.block.time = env.block.time.plus_seconds(25);
+    assert!(execute(deps.as_mut(), env, mock_info("carol", &[Coin::new(10, "uusd")]), bid).is_err());
+}
+
+#[test]
+fn batch_auction(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let start = mock_env().block.time.seconds();
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::BatchAuction{
+                start_time: start,
+                end_time: start + 100,
+                token_cap: Uint128::new(1000)
+            }
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+//-Sealed bids during the window-----------------
+    let bid = |max_price| ExecuteMsg::PlaceBid{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        max_price,
+        kyc: None
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(900, "uusd")]), bid(Decimal::percent(300))).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(1200, "uusd")]), bid(Decimal::percent(200))).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("carol", &[Coin::new(1000, "uusd")]), bid(Decimal::percent(150))).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("dave", &[Coin::new(500, "uusd")]), bid(Decimal::percent(150))).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("erin", &[Coin::new(500, "uusd")]), bid(Decimal::percent(50))).is_err());
+
+    let msg = QueryMsg::GetBids{project_id: Uint128::from(1u64), stage: String::from("ido"), start_after: None, limit: Some(2)};
+    let res: BidsResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.bids.len(), 2);
+    assert_eq!(res.bids[0].1.bidder, Addr::unchecked("alice"));
+    assert_eq!(res.bids[1].1.bidder, Addr::unchecked("bob"));
+
+    let settle = ExecuteMsg::SettleAuction{project_id: Uint128::from(1u64), stage: String::from("ido"), limit: Some(1)};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), settle.clone()).is_err());
+
+//-Settle one bid per call after the window-----------------
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    let mut refunds = Vec::new();
+    let mut calls = 0;
+    loop {
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), settle.clone()).unwrap();
+        for msg in res.messages {
+            if let CosmosMsg::Bank(BankMsg::Send{to_address, amount}) = msg.msg {
+                refunds.push((to_address, amount[0].amount));
+            }
+        }
+        calls += 1;
+        let msg = QueryMsg::GetAuctionState{project_id: Uint128::from(1u64), stage: String::from("ido")};
+        let state: AuctionState = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        if state.phase == AuctionPhase::Settled {
+            assert_eq!(state.clearing_price, Decimal::percent(200));
+            break;
+        }
+    }
+    assert!(calls > 2);
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), settle).is_err());
+
+    //bob is the marginal bid and is partially filled, lower bids are refunded
+    assert_eq!(refunds, vec![
+        (String::from("bob"), Uint128::new(100)),
+        (String::from("dave"), Uint128::new(500)),
+        (String::from("carol"), Uint128::new(1000)),
+    ]);
+
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.ido_users.len(), 2);
+    assert_eq!(res.ido_users[0].total_amount, Uint128::new(450));
+    assert_eq!(res.ido_users[1].total_amount, Uint128::new(550));
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    assert_eq!(res.treasury.total_raised, Uint128::new(2000));
+}
+
+#[test]
+fn lottery_sale(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let start = mock_env().block.time.seconds();
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::Lottery{
+                ticket_price: Uint128::new(100),
+                start_time: start,
+                end_time: start + 100,
+                winning_tickets: 3
+            }
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::SetLotteryWhitelist{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        entries: vec![
+            WhitelistEntry{ wallet: String::from("alice"), max_tickets: 3 },
+            WhitelistEntry{ wallet: String::from("bob"), max_tickets: 2 },
+        ]
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let seed = b"lottery seed".to_vec();
+    let msg = ExecuteMsg::CommitLotterySeed{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        commitment: Sha256::digest(&seed).to_vec().into()
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg.clone()).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).is_err());
+
+//-Whitelisted users register tickets-----------------
+    let register = |tickets| ExecuteMsg::RegisterTickets{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        tickets,
+        kyc: None
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(300, "uusd")]), register(3)).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(100, "uusd")]), register(1)).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(100, "uusd")]), register(1)).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(100, "uusd")]), register(1)).is_err());
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("carol", &[Coin::new(100, "uusd")]), register(1)).is_err());
+
+//-Reveal after registration closes-----------------
+    let reveal = |seed: &[u8]| ExecuteMsg::RevealLotterySeed{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        seed: seed.into()
+    };
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), reveal(&seed)).is_err());
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("project_admin", &[]), reveal(b"wrong")).is_err());
+    execute(deps.as_mut(), env.clone(), mock_info("project_admin", &[]), reveal(&seed)).unwrap();
+
+    let draw = ExecuteMsg::DrawLottery{project_id: Uint128::from(1u64), stage: String::from("ido"), limit: Some(2)};
+    execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), draw.clone()).unwrap();
+    let claim = ExecuteMsg::ClaimAllocationAndRefund{project_id: Uint128::from(1u64), stage: String::from("ido"), kyc: None};
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).is_err());
+    execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), draw.clone()).unwrap();
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), draw).is_err());
+
+//-Redo the draw off-chain from the published randomness-----------------
+    let msg = QueryMsg::GetLottery{project_id: Uint128::from(1u64), stage: String::from("ido")};
+    let lottery: LotteryState = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    let randomness = Sha256::new()
+        .chain(&seed)
+        .chain(env.block.height.to_be_bytes())
+        .chain(env.block.time.nanos().to_be_bytes())
+        .finalize()
+        .to_vec();
+    assert_eq!(lottery.randomness.unwrap().to_vec(), randomness);
+
+    let owners = ["alice", "alice", "alice", "bob", "bob"];
+    let mut swaps: HashMap<u64, u64> = HashMap::new();
+    let mut expected: HashMap<&str, u32> = HashMap::new();
+    for i in 0..3u64 {
+        let hash = Sha256::new().chain(&randomness).chain(i.to_be_bytes()).finalize();
+        let mut r = [0u8; 8];
+        r.copy_from_slice(&hash[..8]);
+        let j = i + u64::from_be_bytes(r) % (5 - i);
+        let ticket = *swaps.get(&j).unwrap_or(&j);
+        let moved = *swaps.get(&i).unwrap_or(&i);
+        swaps.insert(j, moved);
+        *expected.entry(owners[ticket as usize]).or_default() += 1;
+    }
+
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), env.clone(),
+        QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)}).unwrap()).unwrap();
+    for (wallet, won) in expected.iter() {
+        let msg = QueryMsg::GetLotteryEntry{project_id: Uint128::from(1u64), stage: String::from("ido"), wallet: wallet.to_string()};
+        let entry: LotteryEntry = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(entry.won, *won);
+
+        let user = res.ido_users.iter().find(|u| u.wallet_address == *wallet).unwrap();
+        assert_eq!(user.total_amount, Uint128::new(100 * *won as u128));
+    }
+
+//-Losing tickets are refunded once-----------------
+    let alice_won = *expected.get("alice").unwrap_or(&0) as u128;
+    let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).unwrap();
+    if alice_won < 3 {
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+            to_address: String::from("alice"),
+            amount: vec![Coin::new(100 * (3 - alice_won), "uusd")]
+        }));
+    }
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim).is_err());
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    assert_eq!(res.treasury.total_raised, Uint128::new(300));
+}
+
+#[test]
+fn lottery_reveal_deadline(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let start = mock_env().block.time.seconds();
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::Lottery{
+                ticket_price: Uint128::new(100),
+                start_time: start,
+                end_time: start + 100,
+                winning_tickets: 1
+            }
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    let msg = ExecuteMsg::SetLotteryWhitelist{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        entries: vec![WhitelistEntry{ wallet: String::from("alice"), max_tickets: 2 }]
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let seed = b"lottery seed".to_vec();
+    let msg = ExecuteMsg::CommitLotterySeed{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        commitment: Sha256::digest(&seed).to_vec().into()
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    let msg = ExecuteMsg::RegisterTickets{project_id: Uint128::from(1u64), stage: String::from("ido"), tickets: 2, kyc: None};
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(200, "uusd")]), msg).unwrap();
+
+//-No reveal before the deadline keeps the tickets escrowed-----------------
+    let claim = ExecuteMsg::ClaimAllocationAndRefund{project_id: Uint128::from(1u64), stage: String::from("ido"), kyc: None};
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::LotteryNotDrawn { }));
+
+//-After the deadline the seed can't be revealed and all tickets are refunded-----------------
+    env.block.time = env.block.time.plus_seconds(3 * 24 * 60 * 60);
+    let msg = ExecuteMsg::RevealLotterySeed{project_id: Uint128::from(1u64), stage: String::from("ido"), seed: seed.into()};
+    let err = execute(deps.as_mut(), env.clone(), mock_info("project_admin", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::RevealDeadlinePassed { }));
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("alice"),
+        amount: vec![Coin::new(200, "uusd")]
+    }));
+    assert!(execute(deps.as_mut(), env, mock_info("alice", &[]), claim).is_err());
+}
+
+#[test]
+fn staking_tiers(){
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_stakes(&[("alice", 1000), ("bob", 200)]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::FixedPrice
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let tiers = ProjectTiers{
+        snapshot_height: mock_env().block.height,
+        base_allocation: Uint128::new(400),
+        tiers: vec![
+            Tier{
+                min_stake: Uint128::new(100),
+                allocation: TierAllocation::Guaranteed{ amount: Uint128::new(500) },
+                weight: Uint128::new(1)
+            },
+            Tier{
+                min_stake: Uint128::new(1000),
+                allocation: TierAllocation::Multiplier{ multiplier: Decimal::percent(300) },
+                weight: Uint128::new(3)
+            },
+        ]
+    };
+    let msg = ExecuteMsg::SetProjectTiers{project_id: Uint128::from(1u64), tiers: Some(tiers.clone())};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg.clone()).is_err());
+
+    let staking = ExecuteMsg::SetStakingContract{staking_addr: String::from("staking")};
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), staking).unwrap();
+    let unsorted = ExecuteMsg::SetProjectTiers{project_id: Uint128::from(1u64), tiers: Some(ProjectTiers{
+        tiers: tiers.tiers.iter().rev().cloned().collect(),
+        ..tiers.clone()
+    })};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), unsorted).is_err());
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+//-Contributions are capped by the stake tier-----------------
+    let buy = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("ido")), kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(1000, "uusd")]), buy.clone()).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(300, "uusd")]), buy.clone()).is_err());
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(200, "uusd")]), buy.clone()).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(600, "uusd")]), buy.clone()).is_err());
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(500, "uusd")]), buy.clone()).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("carol", &[Coin::new(1, "uusd")]), buy).is_err());
+
+    //imports are checked at the stage price too
+    let msg = ExecuteMsg::ImportUsers{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        entries: vec![import_entry("carol", 100)],
+        mode: ImportMode::Append
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::AllocationExceeded { }));
+
+    let msg = QueryMsg::GetTierAllocation{project_id: Uint128::from(1u64), wallet: String::from("alice")};
+    let res: Option<TierAllocationResponse> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Some(TierAllocationResponse{
+        stake: Uint128::new(1000),
+        weight: Uint128::new(3),
+        cap: Uint128::new(1200),
+        contributed: Uint128::new(1200)
+    }));
+
+//-Bids and tickets count against the same cap-----------------
+    let start = mock_env().block.time.seconds();
+    for (stage, sale_type) in [
+        ("presale", SaleType::BatchAuction{ start_time: start, end_time: start + 100, token_cap: Uint128::new(1000) }),
+        ("seed", SaleType::Lottery{ ticket_price: Uint128::new(100), start_time: start, end_time: start + 100, winning_tickets: 1 }),
+    ] {
+        let msg = ExecuteMsg::SetStageSale{
+            project_id: Uint128::from(1u64),
+            stage: String::from(stage),
+            sale: StageSale{ price: Decimal::one(), payment_decimals: 6, token_decimals: 6, sale_type }
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    }
+    let bid = ExecuteMsg::PlaceBid{project_id: Uint128::from(1u64), stage: String::from("presale"), max_price: Decimal::one(), kyc: None};
+    let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(100, "uusd")]), bid).unwrap_err();
+    assert!(matches!(err, ContractError::AllocationExceeded { }));
+
+    let msg = ExecuteMsg::SetLotteryWhitelist{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        entries: vec![WhitelistEntry{ wallet: String::from("bob"), max_tickets: 1 }]
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    let msg = ExecuteMsg::RegisterTickets{project_id: Uint128::from(1u64), stage: String::from("seed"), tickets: 1, kyc: None};
+    let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(100, "uusd")]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::AllocationExceeded { }));
+
+//-Without tiers the sale is open again-----------------
+    let msg = ExecuteMsg::SetProjectTiers{project_id: Uint128::from(1u64), tiers: None};
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    let buy = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("ido")), kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("carol", &[Coin::new(1, "uusd")]), buy).unwrap();
+}
+
+#[test]
+fn registration_weights(){
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_stakes(&[("alice", 1000), ("bob", 200), ("carol", 150)]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::FixedPrice
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let start = mock_env().block.time.seconds();
+    let registration = ExecuteMsg::SetRegistration{project_id: Uint128::from(1u64), registration: Some(RegistrationConfig{
+        start_time: start,
+        end_time: start + 100,
+        total_allocation: Uint128::new(1000)
+    })};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), registration.clone()).is_err());
+
+    let staking = ExecuteMsg::SetStakingContract{staking_addr: String::from("staking")};
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), staking).unwrap();
+    let msg = ExecuteMsg::SetProjectTiers{project_id: Uint128::from(1u64), tiers: Some(ProjectTiers{
+        snapshot_height: mock_env().block.height,
+        base_allocation: Uint128::zero(),
+        tiers: vec![
+            Tier{
+                min_stake: Uint128::new(100),
+                allocation: TierAllocation::Guaranteed{ amount: Uint128::zero() },
+                weight: Uint128::new(1)
+            },
+            Tier{
+                min_stake: Uint128::new(1000),
+                allocation: TierAllocation::Guaranteed{ amount: Uint128::zero() },
+                weight: Uint128::new(3)
+            },
+        ]
+    })};
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), registration).unwrap();
+
+//-Weights are recorded when users register-----------------
+    let register = ExecuteMsg::Register{project_id: Uint128::from(1u64)};
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), register.clone()).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), register.clone()).is_err());
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), register.clone()).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("dave", &[]), register.clone()).is_err());
+
+    //moving stake after registering does not change the weight
+    deps.querier.with_stakes(&[("alice", 0), ("bob", 0), ("carol", 150)]);
+
+    let buy = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("ido")), kyc: None, referrer: None};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]), buy.clone()).is_err());
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), register).is_err());
+
+    let msg = QueryMsg::GetRegistration{project_id: Uint128::from(1u64)};
+    let res: RegistrationResponse = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.total_weight, Uint128::new(4));
+    assert_eq!(res.registrants, 2);
+
+//-Allocations are the weight share once registration closes-----------------
+    let msg = QueryMsg::GetTierAllocation{project_id: Uint128::from(1u64), wallet: String::from("alice")};
+    let res: Option<TierAllocationResponse> = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res, Some(TierAllocationResponse{
+        stake: Uint128::new(1000),
+        weight: Uint128::new(3),
+        cap: Uint128::new(750),
+        contributed: Uint128::zero()
+    }));
+
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &[Coin::new(750, "uusd")]), buy.clone()).unwrap();
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("bob", &[Coin::new(300, "uusd")]), buy.clone()).is_err());
+    execute(deps.as_mut(), env.clone(), mock_info("bob", &[Coin::new(250, "uusd")]), buy.clone()).unwrap();
+    assert!(execute(deps.as_mut(), env, mock_info("carol", &[Coin::new(1, "uusd")]), buy).is_err());
+}
+
+fn kyc_payload(wallet: &str, expiry: u64) -> Vec<u8> {
+    to_binary(&KycPayload{
+        contract: String::from(MOCK_CONTRACT_ADDR),
+        project_id: Uint128::from(1u64),
+        wallet: String::from(wallet),
+        expiry
+    }).unwrap().to_vec()
+}
+
+#[test]
+fn kyc_approvals(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+
+    let provider = ed25519_zebra::SigningKey::from([7u8; 32]);
+    let pubkey: [u8; 32] = ed25519_zebra::VerificationKey::from(&provider).into();
+    let msg = ExecuteMsg::SetKycConfig{kyc: Some(KycConfig{
+        scheme: KycScheme::Ed25519,
+        pubkey: pubkey.to_vec().into()
+    })};
+    let err = execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { }));
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+    let expiry = mock_env().block.time.seconds() + 100;
+    let sign = |wallet: &str, expiry: u64| KycProof{
+        expiry,
+        signature: <[u8; 64]>::from(provider.sign(&kyc_payload(wallet, expiry))).to_vec().into()
+    };
+    let contribute = |kyc| ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc, referrer: None};
+
+//-Contributions need a valid approval for the sender-----------------
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]), contribute(None)).unwrap_err();
+    assert!(matches!(err, ContractError::KycRequired { }));
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]),
+        contribute(Some(sign("bob", expiry)))).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidKycSignature { }));
+    let mut forged = sign("alice", expiry);
+    forged.expiry += 1;
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]),
+        contribute(Some(forged))).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidKycSignature { }));
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]),
+        contribute(Some(sign("alice", expiry)))).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    let err = execute(deps.as_mut(), env, mock_info("alice", &[Coin::new(100, "uusd")]),
+        contribute(Some(sign("alice", expiry)))).unwrap_err();
+    assert!(matches!(err, ContractError::KycExpired { }));
+
+//-Revoked wallets are rejected until restored-----------------
+    let revoke = |revoked| ExecuteMsg::SetKycRevoked{wallets: vec![String::from("alice")], revoked};
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), revoke(true)).unwrap();
+    let msg = QueryMsg::IsKycRevoked{wallet: String::from("alice")};
+    assert!(from_binary::<bool>(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap());
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]),
+        contribute(Some(sign("alice", expiry)))).unwrap_err();
+    assert!(matches!(err, ContractError::KycRevoked { }));
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), revoke(false)).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]),
+        contribute(Some(sign("alice", expiry)))).unwrap();
+
+//-Claims need an approval too-----------------
+    let claims = |kyc: Option<KycProof>| vec![
+        ExecuteMsg::ClaimYield{project_id: Uint128::from(1u64), kyc: kyc.clone()},
+        ExecuteMsg::ClaimBonus{project_id: Uint128::from(1u64), kyc: kyc.clone()},
+        ExecuteMsg::ClaimReferralRewards{project_id: Uint128::from(1u64), kyc},
+    ];
+    for msg in claims(None) {
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::KycRequired { }));
+    }
+    //approved claims get past KYC and fail for having nothing to claim
+    for msg in claims(Some(sign("alice", expiry))) {
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(!matches!(err, ContractError::KycRequired { } | ContractError::InvalidKycSignature { }));
+    }
+
+//-secp256k1 providers sign the payload hash-----------------
+    use k256::ecdsa::signature::DigestSigner;
+    let provider = k256::ecdsa::SigningKey::from_bytes(&[9u8; 32]).unwrap();
+    let msg = ExecuteMsg::SetKycConfig{kyc: Some(KycConfig{
+        scheme: KycScheme::Secp256k1,
+        pubkey: provider.verifying_key().to_bytes().to_vec().into()
+    })};
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+    let signature: k256::ecdsa::Signature = provider.sign_digest(Sha256::new().chain(kyc_payload("bob", expiry)));
+    let proof = KycProof{ expiry, signature: signature.as_ref().to_vec().into() };
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(100, "uusd")]), contribute(Some(proof))).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), mock_info("carol", &[Coin::new(100, "uusd")]),
+        contribute(Some(sign("carol", expiry)))).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidKycSignature { }));
+
+    let msg = QueryMsg::GetContribution{project_id: Uint128::from(1u64), wallet: String::from("bob")};
+    let res: Uint128 = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Uint128::new(100));
+}
+
+#[test]
+fn deny_lists(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+
+    let deny = |project_id, wallets: &[&str], denied| ExecuteMsg::SetDenied{
+        project_id,
+        wallets: wallets.iter().map(|w| w.to_string()).collect(),
+        denied
+    };
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("compliance", &[]), deny(None, &["alice"], true)).is_err());
+    let msg = ExecuteMsg::SetCompliance{compliance: String::from("compliance")};
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("compliance", &[]), deny(None, &["alice", "carol"], true)).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("compliance", &[]),
+        deny(Some(Uint128::from(1u64)), &["bob"], true)).unwrap();
+
+//-Denied wallets can not contribute or be added-----------------
+    let contribute = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]), contribute.clone()).is_err());
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(100, "uusd")]), contribute.clone()).is_err());
+    execute(deps.as_mut(), mock_env(), mock_info("dave", &[Coin::new(100, "uusd")]), contribute.clone()).unwrap();
+
+    let msg = ExecuteMsg::AddUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("bob"),
+        stage: String::from("seed"),
+        amount: Uint128::new(100)
+    };
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).is_err());
+    let msg = ExecuteMsg::ClaimYield{project_id: Uint128::from(1u64), kyc: None};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).is_err());
+
+    let msg = QueryMsg::IsDenied{project_id: Uint128::from(1u64), wallet: String::from("bob")};
+    assert!(from_binary::<bool>(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap());
+    let msg = QueryMsg::IsDenied{project_id: Uint128::from(2u64), wallet: String::from("bob")};
+    assert!(!from_binary::<bool>(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap());
+
+//-Lists are paginated for audits-----------------
+    let msg = QueryMsg::GetDenyList{project_id: None, start_after: None, limit: Some(1)};
+    let res: DenyListResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.wallets, vec![Addr::unchecked("alice")]);
+    let msg = QueryMsg::GetDenyList{project_id: None, start_after: Some(String::from("alice")), limit: None};
+    let res: DenyListResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.wallets, vec![Addr::unchecked("carol")]);
+    let msg = QueryMsg::GetDenyList{project_id: Some(Uint128::from(1u64)), start_after: None, limit: None};
+    let res: DenyListResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.wallets, vec![Addr::unchecked("bob")]);
+
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), deny(None, &["alice"], false)).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(100, "uusd")]), contribute).unwrap();
+}
+
+#[test]
+fn referral_rewards(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    deposit_tokens(deps.as_mut(), 100_000);
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::FixedPrice
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    let msg = ExecuteMsg::SetReferralRates{project_id: Uint128::from(1u64), rates: Some(ReferralRates{
+        payment_rate: Decimal::percent(5),
+        token_rate: Decimal::percent(10)
+    })};
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+//-Referred contributions accrue rewards-----------------
+    let buy = |referrer: &str| ExecuteMsg::Contribute{
+        project_id: Uint128::from(1u64),
+        stage: Some(String::from("ido")),
+        kyc: None,
+        referrer: Some(String::from(referrer))
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(1000, "uusd")]), buy("ref1")).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[Coin::new(500, "uusd")]), buy("ref2")).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("carol", &[Coin::new(300, "uusd")]), buy("ref2")).unwrap();
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("dave", &[Coin::new(300, "uusd")]), buy("dave")).is_err());
+
+    let msg = QueryMsg::GetTopReferrers{project_id: Uint128::from(1u64), limit: None};
+    let res: TopReferrersResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.referrers.len(), 2);
+    assert_eq!(res.referrers[0].0, Addr::unchecked("ref1"));
+    assert_eq!(res.referrers[1], (Addr::unchecked("ref2"), ReferralRewards{
+        referred: Uint128::new(800),
+        funds: Uint128::new(40),
+        tokens: Uint128::new(80),
+        claimed_funds: Uint128::zero(),
+        claimed_tokens: Uint128::zero()
+    }));
+
+//-Referrers claim funds from the treasury and sale tokens-----------------
+    let claim = ExecuteMsg::ClaimReferralRewards{project_id: Uint128::from(1u64), kyc: None};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("ref1", &[]), claim.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("ref1"),
+        amount: vec![Coin::new(50, "uusd")]
+    }));
+    assert_eq!(res.messages[1].msg, CosmosMsg::Wasm(WasmMsg::Execute{
+        contract_addr: String::from("WeFund"),
+        msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer{
+            recipient: String::from("ref1"),
+            amount: Uint128::new(100)
+        }).unwrap(),
+        funds: Vec::new()
+    }));
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("ref1", &[]), claim.clone()).is_err());
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), claim).is_err());
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.treasury.released, Uint128::new(50));
+}
+
+#[test]
+fn bonus_allocations(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type: SaleType::FixedPrice
+        }
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let start = mock_env().block.time.seconds();
+    let msg = ExecuteMsg::SetBonusRules{project_id: Uint128::from(1u64), stage: String::from("seed"), rules: Some(BonusRules{
+        time_tiers: vec![TimeBonus{ until: start + 50, rate: Decimal::percent(20) }],
+        amount_tiers: vec![
+            AmountBonus{ min_payment: Uint128::new(1000), rate: Decimal::percent(5) },
+            AmountBonus{ min_payment: Uint128::new(5000), rate: Decimal::percent(10) },
+        ],
+        cliff: 10,
+        duration: 100
+    })};
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let msg = QueryMsg::SimulateContribution{project_id: Uint128::from(1u64), stage: String::from("seed"), amount: Uint128::new(5000)};
+    let res: SimulateContributionResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.bonus_amount, Uint128::new(1500));
+
+//-Early and large purchases get bonus tokens-----------------
+    let buy = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("seed")), kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[Coin::new(1000, "uusd")]), buy.clone()).unwrap();
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60);
+    execute(deps.as_mut(), env.clone(), mock_info("bob", &[Coin::new(500, "uusd")]), buy.clone()).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("carol", &[Coin::new(5000, "uusd")]), buy).unwrap();
+
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.seed_users[0].total_amount, Uint128::new(1000));
+    assert_eq!(res.seed_users[0].bonus_amount, Uint128::new(250));
+    assert_eq!(res.seed_users[1].bonus_amount, Uint128::zero());
+    assert_eq!(res.seed_users[2].bonus_amount, Uint128::new(500));
+
+    //admin allocations have no payment, only the time bonus applies
+    let msg = ExecuteMsg::AddSeedUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("dave"),
+        amount: Uint128::new(1000)
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.seed_users[3].bonus_amount, Uint128::new(200));
+
+//-Bonus vests on its own schedule from the project start-----------------
+    let claim = ExecuteMsg::ClaimBonus{project_id: Uint128::from(1u64), kyc: None};
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).is_err());
+    let msg = ExecuteMsg::SetProjectConfig{
+        project_id: Uint128::from(1u64),
+        admin: Some(String::from("project_admin")),
+        token_addr: None,
+        start_time: Some(Uint128::from(start))
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("project_admin", &[]), msg).unwrap();
+
+    //bonus tokens are paid out of the project deposit
+    let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughBalance { }));
+    deposit_tokens(deps.as_mut(), 100_000);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute{
+        contract_addr: String::from("WeFund"),
+        msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer{
+            recipient: String::from("alice"),
+            amount: Uint128::new(125)
+        }).unwrap(),
+        funds: Vec::new()
+    }));
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).is_err());
+    assert!(execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), claim.clone()).is_err());
+
+    env.block.time = env.block.time.plus_seconds(100);
+    let msg = QueryMsg::GetBonus{project_id: Uint128::from(1u64), wallet: String::from("alice")};
+    let res: BonusResponse = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res, BonusResponse{
+        total: Uint128::new(250),
+        released: Uint128::new(125),
+        claimable: Uint128::new(125)
+    });
+    execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), claim.clone()).unwrap();
+
+//-A longer schedule after claims vests nothing new-----------------
+    let msg = ExecuteMsg::SetBonusRules{project_id: Uint128::from(1u64), stage: String::from("seed"), rules: Some(BonusRules{
+        time_tiers: Vec::new(),
+        amount_tiers: Vec::new(),
+        cliff: 10,
+        duration: 10000
+    })};
+    execute(deps.as_mut(), env.clone(), mock_info("project_admin", &[]), msg).unwrap();
+    let msg = QueryMsg::GetBonus{project_id: Uint128::from(1u64), wallet: String::from("alice")};
+    let res: BonusResponse = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.claimable, Uint128::zero());
+    let err = execute(deps.as_mut(), env, mock_info("alice", &[]), claim).unwrap_err();
+    assert!(matches!(err, ContractError::NoBonus { }));
+}
+
+#[test]
+fn liquidity_bootstrap(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    deposit_tokens(deps.as_mut(), 100_000);
+    let info = mock_info("admin", &[]);
+    let msg = ExecuteMsg::AddIDOUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("investor"),
+        amount: Uint128::new(2000)
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1000, "uusd")]), msg).unwrap();
+
+    //half of the raise paired at 0.5 uusd per token
+    let msg = ExecuteMsg::SetLiquidityConfig{
+        project_id: Uint128::from(1u64),
+        liquidity: Some(LiquidityConfig{
+            factory: String::from("factory"),
+            funds_share: Decimal::percent(50),
+            price: Decimal::percent(50),
+            payment_decimals: 6,
+            token_decimals: 6,
+            cliff: 100,
+            duration: 1000
+        })
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+
+    let bootstrap = ExecuteMsg::BootstrapLiquidity{project_id: Uint128::from(1u64)};
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), bootstrap.clone()).is_err());
+    let msg = ExecuteMsg::FinalizeSale{project_id: Uint128::from(1u64)};
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+//-No pair yet, the factory creates it-----------------
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), bootstrap.clone()).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, LIQUIDITY_CREATE_PAIR_REPLY_ID);
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), bootstrap).is_err());
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.locked, Uint128::new(500));
+
+    //LP tokens of another project sharing the pair
+    deps.querier.with_dex_pairs(&[("WeFunduusd", "pair")]);
+    deps.querier.with_token_balances(&[(&String::from("liquidity"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(300))])]);
+    let msg = Reply{
+        id: LIQUIDITY_CREATE_PAIR_REPLY_ID,
+        result: ContractResult::Ok(SubMsgExecutionResponse{
+            events: Vec::new(),
+            data: None
+        })
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute{
+        contract_addr: String::from("WeFund"),
+        msg: to_binary(&cw20::Cw20ExecuteMsg::IncreaseAllowance{
+            spender: String::from("pair"),
+            amount: Uint128::new(1000),
+            expires: None
+        }).unwrap(),
+        funds: Vec::new()
+    }));
+    assert_eq!(res.messages[1].msg, CosmosMsg::Wasm(WasmMsg::Execute{
+        contract_addr: String::from("pair"),
+        msg: to_binary(&PairExecuteMsg::ProvideLiquidity{
+            assets: [
+                Asset{ info: AssetInfo::Token{ contract_addr: String::from("WeFund") },
+                    amount: Uint128::new(1000) },
+                Asset{ info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                    amount: Uint128::new(500) },
+            ],
+            slippage_tolerance: None,
+            receiver: None
+        }).unwrap(),
+        funds: vec![Coin::new(500, "uusd")]
+    }));
+    assert_eq!(res.messages[1].id, LIQUIDITY_PROVIDE_REPLY_ID);
+
+    deps.querier.with_token_balances(&[(&String::from("liquidity"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(1100))])]);
+    let msg = Reply{
+        id: LIQUIDITY_PROVIDE_REPLY_ID,
+        result: ContractResult::Ok(SubMsgExecutionResponse{
+            events: Vec::new(),
+            data: None
+        })
+    };
+    reply(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let msg = QueryMsg::GetLiquidityPosition{project_id: Uint128::from(1u64)};
+    let res: Option<LiquidityPosition> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    let position = res.unwrap();
+    assert_eq!(position.lp_token, Some(Addr::unchecked("liquidity")));
+    assert_eq!(position.lp_amount, Uint128::new(800));
+
+//-Only the LP tokens of the project vest to its wallet-----------------
+    let claim = ExecuteMsg::ClaimLpTokens{project_id: Uint128::from(1u64)};
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(50);
+    assert!(execute(deps.as_mut(), env.clone(), info.clone(), claim.clone()).is_err());
+
+    env.block.time = env.block.time.plus_seconds(550);
+    let res = execute(deps.as_mut(), env, info, claim).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute{
+        contract_addr: String::from("liquidity"),
+        msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer{
+            recipient: String::from("project_wallet"),
+            amount: Uint128::new(400)
+        }).unwrap(),
+        funds: Vec::new()
+    }));
+}
+
+fn import_entry(wallet: &str, amount: u128) -> UserInfo {
+    UserInfo{
+        wallet_address: Addr::unchecked(wallet),
+        total_amount: Uint128::new(amount),
+        released_amount: Uint128::zero(),
+        pending_amount: Uint128::zero(),
+        bonus_amount: Uint128::zero(),
+        bonus_released: Uint128::zero(),
+    }
+}
+
+#[test]
+fn import_users(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), None);
+    let info = mock_info("project_admin", &[]);
+
+    let msg = ExecuteMsg::AddSeedUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("alice"),
+        amount: Uint128::new(100)
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+//-Append merges duplicates-----------------
+    let msg = ExecuteMsg::ImportUsers{
+        project_id: Uint128::from(1u64),
+        stage: String::from("Seed"),
+        entries: vec![import_entry("alice", 50), import_entry("bob", 200), import_entry("bob", 300)],
+        mode: ImportMode::Append
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { }));
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let attr = |key: &str| res.attributes.iter().find(|a| a.key == key).unwrap().value.clone();
+    assert_eq!(attr("imported"), "3");
+    assert_eq!(attr("added"), "1");
+    assert_eq!(attr("merged"), "2");
+
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.seed_users.len(), 2);
+    assert_eq!(res.seed_users[0].total_amount, Uint128::new(150));
+    assert_eq!(res.seed_users[1].total_amount, Uint128::new(500));
+
+//-Invalid entries are rejected-----------------
+    let mut released = import_entry("carol", 100);
+    released.released_amount = Uint128::new(10);
+    for entry in [released, import_entry("carol", 0), import_entry("ab", 100)] {
+        let msg = ExecuteMsg::ImportUsers{
+            project_id: Uint128::from(1u64),
+            stage: String::from("seed"),
+            entries: vec![entry],
+            mode: ImportMode::Append
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidImportEntry { .. } | ContractError::InvalidAddress { .. }));
+    }
+
+//-Replace and the stage token cap-----------------
+    let sale = |sale_type: SaleType| ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        sale: StageSale{
+            price: Decimal::one(),
+            payment_decimals: 6,
+            token_decimals: 6,
+            sale_type
+        }
+    };
+    let import = |wallet: &str, amount: u128, mode: ImportMode| ExecuteMsg::ImportUsers{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        entries: vec![import_entry(wallet, amount)],
+        mode
+    };
+    //settled stages allocate their own cap
+    execute(deps.as_mut(), mock_env(), info.clone(), sale(SaleType::BatchAuction{
+        start_time: 0, end_time: 1, token_cap: Uint128::new(400)
+    })).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), import("carol", 300, ImportMode::Append)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSaleType { }));
+
+    execute(deps.as_mut(), mock_env(), info.clone(), sale(SaleType::DutchAuction{
+        start_price: Decimal::percent(200), start_time: 0, end_time: 1, token_cap: Uint128::new(400)
+    })).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), import("carol", 300, ImportMode::Append)).unwrap_err();
+    assert!(matches!(err, ContractError::AllocationExceeded { }));
+    execute(deps.as_mut(), mock_env(), info.clone(), import("carol", 300, ImportMode::Replace)).unwrap();
+
+    //imported tokens stay sold, a later replace can't sell them again
+    let msg = QueryMsg::GetCurrentPrice{project_id: Uint128::from(1u64), stage: String::from("seed")};
+    let res: CurrentPriceResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.tokens_sold, Uint128::new(300));
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), import("dave", 150, ImportMode::Replace)).unwrap_err();
+    assert!(matches!(err, ContractError::StageSoldOut { }));
+
+//-Imports stop once the vesting contract has the allocations-----------------
+    let msg = ExecuteMsg::SetConfig{
+        admin: String::from("admin"),
+        vesting_addr: Some(String::from("vesting_contract")),
+        vesting_code_id: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    let msg = ExecuteMsg::StartVesting{project_id: Uint128::from(1u64)};
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::ImportUsers{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        entries: vec![import_entry("dave", 50)],
+        mode: ImportMode::Append
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::VestingStarted { }));
+
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.seed_users, vec![import_entry("carol", 300)]);
+}
+
+#[test]
+fn change_user_allocations(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), None);
+    let info = mock_info("project_admin", &[]);
+
+    let msg = ExecuteMsg::SetSeedUsers{
+        project_id: Uint128::from(1u64),
+        user_infos: vec![import_entry("alice", 1000), import_entry("bob", 500)]
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::SetUserAllocation{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        wallet: String::from("alice"),
+        amount: Uint128::new(600)
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { }));
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let event = &res.events[0];
+    assert_eq!(event.ty, "allocation_changed");
+    assert!(event.attributes.iter().any(|a| a.key == "old_amount" && a.value == "1000"));
+    assert!(event.attributes.iter().any(|a| a.key == "new_amount" && a.value == "600"));
+
+    let msg = ExecuteMsg::RemoveUser{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        wallet: String::from("bob")
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::UserNotFound { }));
+
+//-A claimed bonus bounds the allocation-----------------
+    let start = mock_env().block.time.seconds();
+    let msg = ExecuteMsg::SetBonusRules{project_id: Uint128::from(1u64), stage: String::from("seed"), rules: Some(BonusRules{
+        time_tiers: vec![TimeBonus{ until: start + 50, rate: Decimal::percent(20) }],
+        amount_tiers: Vec::new(),
+        cliff: 0,
+        duration: 0
+    })};
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::AddSeedUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("carol"),
+        amount: Uint128::new(1000)
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::SetProjectConfig{
+        project_id: Uint128::from(1u64),
+        admin: Some(String::from("project_admin")),
+        token_addr: None,
+        start_time: Some(Uint128::from(start))
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    deposit_tokens(deps.as_mut(), 100_000);
+    let claim = ExecuteMsg::ClaimBonus{project_id: Uint128::from(1u64), kyc: None};
+    execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), claim.clone()).unwrap();
+
+    let msg = ExecuteMsg::SetUserAllocation{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        wallet: String::from("carol"),
+        amount: Uint128::new(500)
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::AllocationsReleased { }));
+    //removing and re-adding the wallet would pay the bonus twice
+    let msg = ExecuteMsg::RemoveUser{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        wallet: String::from("carol")
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::AllocationsReleased { }));
+    let err = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), claim).unwrap_err();
+    assert!(matches!(err, ContractError::NoBonus { }));
+
+    //the bonus scales with the allocation
+    let msg = ExecuteMsg::SetUserAllocation{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        wallet: String::from("carol"),
+        amount: Uint128::new(2000)
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+//-Allocations are frozen once the vesting contract has them-----------------
+    let msg = ExecuteMsg::SetConfig{
+        admin: String::from("admin"),
+        vesting_addr: Some(String::from("vesting_contract")),
+        vesting_code_id: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+    let msg = ExecuteMsg::StartVesting{project_id: Uint128::from(1u64)};
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::SetUserAllocation{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        wallet: String::from("alice"),
+        amount: Uint128::new(300)
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::VestingStarted { }));
+    let msg = ExecuteMsg::RemoveUser{
+        project_id: Uint128::from(1u64),
+        stage: String::from("seed"),
+        wallet: String::from("alice")
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::VestingStarted { }));
+
+    let msg = QueryMsg::GetProjectInfo{project_id: Uint128::from(1u64)};
+    let res: ProjectInfo = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.seed_users.len(), 2);
+    assert_eq!(res.seed_users[0].total_amount, Uint128::new(600));
+    assert_eq!(res.seed_users[1].bonus_amount, Uint128::new(400));
+    assert_eq!(res.seed_users[1].bonus_released, Uint128::new(200));
+}
+
+#[test]
+fn cancel_and_archive_project(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), Some(TreasuryParams{
+        wallet: String::from("project_wallet"),
+        denom: String::from("uusd"),
+        milestones: Vec::new()
+    }));
+    let info = mock_info("admin", &[]);
+    let msg = ExecuteMsg::AddProject{
+        project_id: Some(Uint128::from(1u64)),
+        admin: String::from("other_admin"),
+        token_addr: String::from("Other"),
+        start_time: None,
+        vesting_params: Vec::new(),
+        treasury: None,
+        metadata: None
+    };
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), msg).is_err());
+
+    let msg = ExecuteMsg::AddIDOUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("investor"),
+        amount: Uint128::new(2000)
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let contribute = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: None, kyc: None, referrer: None};
+    execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1000, "uusd")]), contribute.clone()).unwrap();
+
+    let archive = ExecuteMsg::ArchiveProject{project_id: Uint128::from(1u64), archived: true};
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), archive.clone()).is_err());
+
+//-Cancel returns the deposited tokens-----------------
+    let deposit = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg{
+        sender: String::from("project_admin"),
+        amount: Uint128::new(5000),
+        msg: to_binary(&Cw20HookMsg::DepositTokens{project_id: Uint128::from(1u64)}).unwrap()
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info("Other", &[]), deposit.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::WrongToken { }));
+    execute(deps.as_mut(), mock_env(), mock_info("WeFund", &[]), deposit).unwrap();
+
+    //tokens held for other projects stay in the contract
+    deps.querier.with_token_balances(&[(&String::from("WeFund"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(9000))])]);
+    let msg = ExecuteMsg::CancelProject{project_id: Uint128::from(1u64)};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg.clone()).is_err());
+    let res = execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute{
+        contract_addr: String::from("WeFund"),
+        msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer{
+            recipient: String::from("project_admin"),
+            amount: Uint128::new(5000)
+        }).unwrap(),
+        funds: Vec::new()
+    }));
+    assert!(execute(deps.as_mut(), mock_env(), info.clone(), msg).is_err());
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1000, "uusd")]), contribute).is_err());
+
+    let msg = QueryMsg::GetProjectTokens{project_id: Uint128::from(1u64)};
+    let res: Uint128 = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Uint128::zero());
+
+//-Contributors are refunded in full-----------------
+    let msg = ExecuteMsg::ClaimRefund{project_id: Uint128::from(1u64)};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send{
+        to_address: String::from("investor"),
+        amount: vec![Coin::new(1000, "uusd")]
+    }));
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg).is_err());
+
+    let msg = QueryMsg::GetTreasury{project_id: Uint128::from(1u64)};
+    let res: TreasuryResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.locked, Uint128::zero());
+
+//-Archived projects leave the listing-----------------
+    execute(deps.as_mut(), mock_env(), info, archive).unwrap();
+    let msg = QueryMsg::GetAllProjectInfo{};
+    let res: Vec<ProjectInfo> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert!(res.is_empty());
+    let msg = QueryMsg::GetArchivedProjects{};
+    let res: Vec<Uint128> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, vec![Uint128::from(1u64)]);
+}
+
+#[test]
+fn project_ids_and_metadata(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), None);
+    let info = mock_info("admin", &[]);
+    let metadata = ProjectMetadata{
+        name: String::from("WeFund"),
+        description: String::from("Crowdfunding"),
+        website: Some(String::from("https://wefund.app")),
+        logo_uri: None,
+        socials: vec![SocialLink{ platform: String::from("twitter"), url: String::from("https://twitter.com/wefund") }]
+    };
+
+//-Unset ids take the next free one-----------------
+    let add = |project_id: Option<Uint128>, metadata: Option<ProjectMetadata>| ExecuteMsg::AddProject{
+        project_id,
+        admin: String::from("project_admin"),
+        token_addr: String::from("WeFund"),
+        start_time: None,
+        vesting_params: Vec::new(),
+        treasury: None,
+        metadata
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), add(None, Some(metadata.clone()))).unwrap();
+    assert!(res.attributes.iter().any(|a| a.key == "project_id" && a.value == "2"));
+    execute(deps.as_mut(), mock_env(), info.clone(), add(Some(Uint128::new(3)), None)).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), add(Some(Uint128::new(3)), None)).unwrap_err();
+    assert!(matches!(err, ContractError::DuplicateProject { }));
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), add(None, None)).unwrap();
+    assert!(res.attributes.iter().any(|a| a.key == "project_id" && a.value == "4"));
+
+    //a large explicit id doesn't use up the auto ids
+    execute(deps.as_mut(), mock_env(), info.clone(), add(Some(Uint128::MAX), None)).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), add(None, None)).unwrap();
+    assert!(res.attributes.iter().any(|a| a.key == "project_id" && a.value == "5"));
+
+    let msg = QueryMsg::GetProjectCount{};
+    let res: Uint128 = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Uint128::new(5));
+    let msg = QueryMsg::GetProjectMetadata{project_id: Uint128::new(2)};
+    let res: Option<ProjectMetadata> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Some(metadata.clone()));
+
+//-Metadata updates are size limited-----------------
+    let mut long = metadata.clone();
+    long.description = "x".repeat(2000);
+    let msg = ExecuteMsg::SetProjectMetadata{project_id: Uint128::new(2), metadata: long};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).is_err());
+
+    let mut updated = metadata;
+    updated.logo_uri = Some(String::from("ipfs://logo"));
+    let msg = ExecuteMsg::SetProjectMetadata{project_id: Uint128::new(2), metadata: updated.clone()};
+    assert!(execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg.clone()).is_err());
+    execute(deps.as_mut(), mock_env(), mock_info("project_admin", &[]), msg).unwrap();
+    let msg = QueryMsg::GetProjectMetadata{project_id: Uint128::new(2)};
+    let res: Option<ProjectMetadata> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res, Some(updated));
+}
+
+#[test]
+fn structured_events(){
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg{
+        admin: Some(String::from("admin")),
+        vesting_code_id: None,
+    };
+    let info = mock_info("admin", &[]);
+    instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::AddProject{
+        project_id: None,
+        admin: String::from("project_admin"),
+        token_addr: String::from("WeFund"),
+        start_time: None,
+        vesting_params: Vec::new(),
+        treasury: Some(TreasuryParams{
+            wallet: String::from("project_wallet"),
+            denom: String::from("uusd"),
+            milestones: Vec::new()
+        }),
+        metadata: None
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.events, vec![Event::new("project_added")
+        .add_attribute("project_id", "1")
+        .add_attribute("admin", "project_admin")
+        .add_attribute("token_addr", "WeFund")]);
+
+//-Purchases change the allocation and report the raise-----------------
+    let msg = ExecuteMsg::SetStageSale{
+        project_id: Uint128::from(1u64),
+        stage: String::from("ido"),
+        sale: StageSale{ price: Decimal::one(), payment_decimals: 6, token_decimals: 6,
+            sale_type: SaleType::FixedPrice }
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Contribute{project_id: Uint128::from(1u64), stage: Some(String::from("ido")), kyc: None, referrer: None};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("investor", &[Coin::new(1000, "uusd")]), msg).unwrap();
+    assert_eq!(res.events, vec![
+        Event::new("allocation_changed")
+            .add_attribute("project_id", "1")
+            .add_attribute("stage", "ido")
+            .add_attribute("wallet", "investor")
+            .add_attribute("old_amount", "0")
+            .add_attribute("new_amount", "1000")
+            .add_attribute("changed_by", "investor"),
+        Event::new("contribution")
+            .add_attribute("project_id", "1")
+            .add_attribute("stage", "ido")
+            .add_attribute("wallet", "investor")
+            .add_attribute("kind", "purchase")
+            .add_attribute("amount", "1000")
+            .add_attribute("token_amount", "1000")
+            .add_attribute("total_raised", "1000"),
+    ]);
+
+//-Replacing stage users reports every changed wallet-----------------
+    let msg = ExecuteMsg::SetIDOUsers{
+        project_id: Uint128::from(1u64),
+        user_infos: vec![import_entry("investor", 1000), import_entry("bob", 300)]
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(res.events.len(), 1);
+    assert!(res.events[0].attributes.iter().any(|a| a.key == "wallet" && a.value == "bob"));
+
+//-Claims-----------------
+    deps.querier.with_token_balances(&[(&String::from("WeFund"), &[])]);
+    let msg = ExecuteMsg::CancelProject{project_id: Uint128::from(1u64)};
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = ExecuteMsg::ClaimRefund{project_id: Uint128::from(1u64)};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("investor", &[]), msg).unwrap();
+    assert_eq!(res.events, vec![Event::new("claim")
+        .add_attribute("project_id", "1")
+        .add_attribute("wallet", "investor")
+        .add_attribute("kind", "refund")
+        .add_attribute("asset", "uusd")
+        .add_attribute("amount", "1000")]);
+}
+
+#[test]
+fn typed_errors(){
+    let mut deps = mock_dependencies(&[]);
+    init_project(deps.as_mut(), None);
+    let info = mock_info("admin", &[]);
+
+    //unset admin keeps the project admin
+    let msg = ExecuteMsg::SetProjectConfig{
+        project_id: Uint128::from(1u64),
+        admin: None,
+        token_addr: None,
+        start_time: Some(Uint128::new(100))
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = QueryMsg::GetConfig{project_id: Uint128::from(1u64)};
+    let res: Config = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.owner, Addr::unchecked("project_admin"));
+
+    let msg = ExecuteMsg::SetProjectConfig{
+        project_id: Uint128::from(1u64),
+        admin: Some(String::from("ab")),
+        token_addr: None,
+        start_time: None
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidAddress { .. }));
+
+    let msg = ExecuteMsg::AddUser{
+        project_id: Uint128::from(2u64),
+        wallet: Addr::unchecked("investor"),
+        stage: String::from("seed"),
+        amount: Uint128::new(100)
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::ProjectNotFound { .. }));
+
+    let msg = ExecuteMsg::AddUser{
+        project_id: Uint128::from(1u64),
+        wallet: Addr::unchecked("investor"),
+        stage: String::from("private"),
+        amount: Uint128::new(100)
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidStage { .. }));
+
+    let msg = ExecuteMsg::AddProject{
+        project_id: Some(Uint128::from(1u64)),
+        admin: String::from("project_admin"),
+        token_addr: String::from("WeFund"),
+        start_time: None,
+        vesting_params: Vec::new(),
+        treasury: None,
+        metadata: None
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::DuplicateProject { }));
+}